@@ -0,0 +1,178 @@
+use diff::{Context, Differ, Path, PathFrame};
+use node::NodeChildren;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct A11yProps {
+    pub name: Option<String>,
+    pub value: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Accessible properties of a params type. `define_params!` implements it for every params
+/// struct from the fields marked with `#[a11y = "label"]`, `#[a11y = "value"]` and
+/// `#[a11y = "description"]`. The label becomes the accessible name.
+pub trait AccessibleParams {
+    fn a11y_props(&self) -> A11yProps;
+}
+
+/// Implemented for `AllNodes` by `define_nodes!`, using the role declared with
+/// `#[role = "..."]` on a node and the props of its params. Text nodes have the role `text`.
+pub trait AccessibleNode: NodeChildren {
+    /// Nodes without a role are left out of the accessibility tree, their accessible
+    /// descendants take their place.
+    fn a11y_role(&self) -> Option<&'static str>;
+    fn a11y_props(&self) -> A11yProps;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct A11yNode {
+    /// Path of the node in the vtree, stable as long as the node's keys are.
+    pub id: Path,
+    pub role: &'static str,
+    pub props: A11yProps,
+    pub children: Vec<A11yNode>,
+}
+
+/// Builds the accessibility tree of an expanded tree.
+pub fn tree<AN: AccessibleNode>(root: &AN) -> Vec<A11yNode> {
+    subtree(&PathFrame::new(root))
+}
+
+/// Builds the accessibility trees of the node at `frame` and its descendants.
+pub fn subtree<AN: AccessibleNode>(frame: &PathFrame<AN>) -> Vec<A11yNode> {
+    let mut nodes = Vec::new();
+    collect(frame.node(), frame.to_path(), &mut nodes);
+    nodes
+}
+
+fn collect<AN: AccessibleNode>(node: &AN, path: Path, nodes: &mut Vec<A11yNode>) {
+    let mut children = Vec::new();
+    node.for_each_child(|entry, child| {
+        collect(child, path.join(entry.to_path_entry()), &mut children);
+    });
+    match node.a11y_role() {
+        Some(role) => {
+            nodes.push(A11yNode {
+                id: path,
+                role: role,
+                props: node.a11y_props(),
+                children: children,
+            })
+        }
+        None => nodes.extend(children),
+    }
+}
+
+/// Id of the closest accessible ancestor of `frame`.
+fn accessible_parent<AN: AccessibleNode>(frame: &PathFrame<AN>) -> Option<Path> {
+    frame.iter().skip(1).find(|pf| pf.node().a11y_role().is_some()).map(|pf| pf.to_path())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum A11yUpdate {
+    /// Nodes added below the accessible node `parent`, `None` being the top level.
+    Added {
+        parent: Option<Path>,
+        nodes: Vec<A11yNode>,
+    },
+    Removed {
+        ids: Vec<Path>,
+    },
+    Changed {
+        id: Path,
+        role: &'static str,
+        props: A11yProps,
+    },
+    /// New order of the accessible children of `parent`.
+    Reordered {
+        parent: Option<Path>,
+        children: Vec<Path>,
+    },
+}
+
+/// Collects incremental updates of the accessibility tree as a `Differ`.
+#[derive(Debug, Default)]
+pub struct A11yUpdates {
+    updates: Vec<A11yUpdate>,
+}
+
+impl A11yUpdates {
+    pub fn new() -> A11yUpdates {
+        A11yUpdates::default()
+    }
+
+    /// Returns the updates collected since the last call.
+    pub fn take(&mut self) -> Vec<A11yUpdate> {
+        ::std::mem::replace(&mut self.updates, Vec::new())
+    }
+}
+
+impl<CTX, AN: AccessibleNode> Differ<CTX, AN> for A11yUpdates {
+    fn diff_added(&mut self, _ctx: &mut Context<CTX, AN>, curr: &PathFrame<AN>) {
+        let nodes = subtree(curr);
+        if !nodes.is_empty() {
+            self.updates.push(A11yUpdate::Added {
+                parent: accessible_parent(curr),
+                nodes: nodes,
+            });
+        }
+    }
+
+    fn diff_removed(&mut self, _ctx: &mut Context<CTX, AN>, last: &PathFrame<AN>) {
+        let ids: Vec<_> = subtree(last).into_iter().map(|n| n.id).collect();
+        if !ids.is_empty() {
+            self.updates.push(A11yUpdate::Removed { ids: ids });
+        }
+    }
+
+    fn diff_replaced(
+        &mut self,
+        ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        last: &PathFrame<AN>,
+    ) {
+        self.diff_removed(ctx, last);
+        self.diff_added(ctx, curr);
+    }
+
+    fn diff_params_changed(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        last: &PathFrame<AN>,
+    ) {
+        let role = match curr.node().a11y_role() {
+            Some(role) => role,
+            None => return,
+        };
+        let props = curr.node().a11y_props();
+        if last.node().a11y_role() != Some(role) || last.node().a11y_props() != props {
+            self.updates.push(A11yUpdate::Changed {
+                id: curr.to_path(),
+                role: role,
+                props: props,
+            });
+        }
+    }
+
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        parent: &PathFrame<AN>,
+        _slot: Option<&'static str>,
+        mut indices: I,
+    ) {
+        if indices.next().is_none() {
+            return;
+        }
+        let (parent_id, children) =
+            match parent.iter().find(|pf| pf.node().a11y_role().is_some()) {
+                Some(pf) => (Some(pf.to_path()), subtree(pf).remove(0).children),
+                None => (None, subtree(parent.iter().last().unwrap())),
+            };
+        self.updates.push(A11yUpdate::Reordered {
+            parent: parent_id,
+            children: children.into_iter().map(|n| n.id).collect(),
+        });
+    }
+}
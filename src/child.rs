@@ -5,7 +5,6 @@ use std::ops::{Deref, DerefMut};
 use key::Key;
 use node;
 use std::option::Option as StdOption;
-use itertools::Itertools;
 
 #[derive(Debug, Clone)]
 pub struct Single<G, AN>
@@ -52,6 +51,15 @@ impl<G, AN> From<G> for Single<G, AN>
     }
 }
 
+impl<G, AN> PartialEq for Single<G, AN>
+    where G: Into<AN>,
+          AN: PartialEq
+{
+    fn eq(&self, other: &Single<G, AN>) -> bool {
+        self.node == other.node
+    }
+}
+
 
 #[derive(Debug, Clone)]
 pub struct Option<G, AN>
@@ -93,6 +101,14 @@ impl<G, AN> Deref for Option<G, AN>
     }
 }
 
+impl<G, AN> DerefMut for Option<G, AN>
+    where G: Into<AN>
+{
+    fn deref_mut(&mut self) -> &mut StdOption<Box<AN>> {
+        &mut self.node
+    }
+}
+
 impl<G, AN> From<StdOption<G>> for Option<G, AN>
     where G: Into<AN>
 {
@@ -109,6 +125,15 @@ impl<G, AN> From<G> for Option<G, AN>
     }
 }
 
+impl<G, AN> PartialEq for Option<G, AN>
+    where G: Into<AN>,
+          AN: PartialEq
+{
+    fn eq(&self, other: &Option<G, AN>) -> bool {
+        self.node == other.node
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Multi<G, AN>
     where G: Into<AN>
@@ -179,23 +204,41 @@ impl<G, AN> Multi<G, AN>
             }))
     }
 
+    /// The reorders turning the kept children in their last order, with the added ones inserted
+    /// at their curr index in curr order, into the curr order, see `Differ::diff_reordered`.
     pub fn diff_reordered<'a>(&'a self,
                     last: &'a Multi<G, AN>)
                     -> impl Iterator<Item = (usize, usize)> + 'a {
-        let curr_it = self.nodes
+        let mut added = self.nodes
             .keys()
             .enumerate()
-            .filter(move |&(_, k)| !last.nodes.contains_key(k));
-
-        let last_it = last.nodes
+            .filter(move |&(_, k)| !last.nodes.contains_key(k))
+            .peekable();
+        let mut kept = last.nodes
             .keys()
             .filter(move |k| self.nodes.contains_key(k))
-            .enumerate();
+            .peekable();
+
+        // an added child takes its curr index, the kept ones fill the gaps in their last order
+        let mut order = Vec::with_capacity(self.nodes.len());
+        loop {
+            let key = match added.peek() {
+                Some(&(c_index, _)) if c_index <= order.len() || kept.peek().is_none() => {
+                    added.next().unwrap().1
+                }
+                _ => {
+                    match kept.next() {
+                        Some(key) => key,
+                        None => break,
+                    }
+                }
+            };
+            order.push(key);
+        }
 
-        curr_it
-            .merge_by(last_it, |a, b| a.0 <= b.0)
+        order.into_iter()
             .enumerate()
-            .map(move |(l_index, (_, l_key))| {
+            .map(move |(l_index, l_key)| {
                 let c_index = self.nodes.get_pair_index(l_key).unwrap().0;
                 (c_index, l_index)
             })
@@ -203,6 +246,16 @@ impl<G, AN> Multi<G, AN>
     }
 }
 
+/// Compares the children in order, a reordered `Multi` isn't equal.
+impl<G, AN> PartialEq for Multi<G, AN>
+    where G: Into<AN>,
+          AN: PartialEq
+{
+    fn eq(&self, other: &Multi<G, AN>) -> bool {
+        self.nodes.len() == other.nodes.len() && self.nodes.iter().eq(other.nodes.iter())
+    }
+}
+
 impl <G, AN> Default for Multi<G, AN>
     where G: Into<AN>
 {
@@ -253,26 +306,28 @@ impl <G, AN, IME, I> From<I> for Multi<G, AN>
 }
 
 
-pub struct SingleBuilder<PB, G, AN>
-    where PB: node::BuilderSetter<node::BuilderChild, Single<G, AN>>,
+pub struct SingleBuilder<PB, G, AN, K = node::BuilderChild>
+    where PB: node::BuilderSetter<K, Single<G, AN>>,
           G: Into<AN>
 {
     parent_builder: PB,
     child: StdOption<Single<G, AN>>,
+    pd: PhantomData<K>,
 }
 
-impl <PB, G, AN> SingleBuilder<PB, G, AN>
-    where PB: node::BuilderSetter<node::BuilderChild, Single<G, AN>>,
+impl <PB, G, AN, K> SingleBuilder<PB, G, AN, K>
+    where PB: node::BuilderSetter<K, Single<G, AN>>,
           G: Into<AN>
 {
-    pub fn new(parent_builder: PB) -> SingleBuilder<PB, G, AN> {
+    pub fn new(parent_builder: PB) -> SingleBuilder<PB, G, AN, K> {
         SingleBuilder {
             parent_builder: parent_builder,
             child: None,
+            pd: PhantomData,
         }
     }
 
-    pub fn add(mut self, _key: Key, child: G) -> SingleBuilder<PB, G, AN> {
+    pub fn add(mut self, _key: Key, child: G) -> SingleBuilder<PB, G, AN, K> {
         assert!(self.child.is_none(), "Child already set");
         self.child = Some(Single::new(child));
         self
@@ -288,26 +343,28 @@ impl <PB, G, AN> SingleBuilder<PB, G, AN>
 }
 
 
-pub struct OptionBuilder<PB, G, AN>
-    where PB: node::BuilderSetter<node::BuilderChild, Option<G, AN>>,
+pub struct OptionBuilder<PB, G, AN, K = node::BuilderChild>
+    where PB: node::BuilderSetter<K, Option<G, AN>>,
           G: Into<AN>
 {
     parent_builder: PB,
     child: Option<G, AN>,
+    pd: PhantomData<K>,
 }
 
-impl <PB, G, AN> OptionBuilder<PB, G, AN>
-    where PB: node::BuilderSetter<node::BuilderChild, Option<G, AN>>,
+impl <PB, G, AN, K> OptionBuilder<PB, G, AN, K>
+    where PB: node::BuilderSetter<K, Option<G, AN>>,
           G: Into<AN>
 {
-    pub fn new(parent_builder: PB) -> OptionBuilder<PB, G, AN> {
+    pub fn new(parent_builder: PB) -> OptionBuilder<PB, G, AN, K> {
         OptionBuilder {
             parent_builder: parent_builder,
             child: Option::new(None),
+            pd: PhantomData,
         }
     }
 
-    pub fn add(mut self, _key: Key, child: G) -> OptionBuilder<PB, G, AN> {
+    pub fn add(mut self, _key: Key, child: G) -> OptionBuilder<PB, G, AN, K> {
         assert!(self.child.is_none(), "Child already set");
         self.child = Option::new(Some(child));
         self
@@ -320,26 +377,28 @@ impl <PB, G, AN> OptionBuilder<PB, G, AN>
 }
 
 
-pub struct MultiBuilder<PB, G, AN>
-    where PB: node::BuilderSetter<node::BuilderChild, Multi<G, AN>>,
+pub struct MultiBuilder<PB, G, AN, K = node::BuilderChild>
+    where PB: node::BuilderSetter<K, Multi<G, AN>>,
           G: Into<AN>
 {
     parent_builder: PB,
     child: Multi<G, AN>,
+    pd: PhantomData<K>,
 }
 
-impl <PB, G, AN> MultiBuilder<PB, G, AN>
-    where PB: node::BuilderSetter<node::BuilderChild, Multi<G, AN>>,
+impl <PB, G, AN, K> MultiBuilder<PB, G, AN, K>
+    where PB: node::BuilderSetter<K, Multi<G, AN>>,
           G: Into<AN>
 {
-    pub fn new(parent_builder: PB) -> MultiBuilder<PB, G, AN> {
+    pub fn new(parent_builder: PB) -> MultiBuilder<PB, G, AN, K> {
         MultiBuilder {
             parent_builder: parent_builder,
             child: Multi::new(),
+            pd: PhantomData,
         }
     }
 
-    pub fn add(mut self, key: Key, child: G) -> MultiBuilder<PB, G, AN> {
+    pub fn add(mut self, key: Key, child: G) -> MultiBuilder<PB, G, AN, K> {
         self.child.push(key, child);
         self
     }
@@ -349,3 +408,30 @@ impl <PB, G, AN> MultiBuilder<PB, G, AN>
         self.parent_builder
     }
 }
+
+/// Implemented by the slot builders, `Group` being the group, or node for a slot of a single
+/// node type, the slot accepts.
+pub trait SlotBuilder {
+    type Group;
+}
+
+impl <PB, G, AN, K> SlotBuilder for SingleBuilder<PB, G, AN, K>
+    where PB: node::BuilderSetter<K, Single<G, AN>>,
+          G: Into<AN>
+{
+    type Group = G;
+}
+
+impl <PB, G, AN, K> SlotBuilder for OptionBuilder<PB, G, AN, K>
+    where PB: node::BuilderSetter<K, Option<G, AN>>,
+          G: Into<AN>
+{
+    type Group = G;
+}
+
+impl <PB, G, AN, K> SlotBuilder for MultiBuilder<PB, G, AN, K>
+    where PB: node::BuilderSetter<K, Multi<G, AN>>,
+          G: Into<AN>
+{
+    type Group = G;
+}
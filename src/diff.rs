@@ -10,6 +10,37 @@ pub enum PathIndexEntry {
     Key(Key, usize),
     /// Used for Single and Option children.
     Empty,
+    /// Used for Multi children of a named slot.
+    SlotKey(&'static str, Key, usize),
+    /// Used for Single and Option children of a named slot.
+    Slot(&'static str),
+}
+
+impl PathIndexEntry {
+    pub fn to_path_entry(&self) -> PathEntry {
+        match *self {
+            PathIndexEntry::Key(ref key, _) => PathEntry::Key(key.clone()),
+            PathIndexEntry::Empty => PathEntry::Empty,
+            PathIndexEntry::SlotKey(slot, ref key, _) => PathEntry::SlotKey(slot, key.clone()),
+            PathIndexEntry::Slot(slot) => PathEntry::Slot(slot),
+        }
+    }
+
+    /// Name of the slot or `None` for the `children` of a node without named slots.
+    pub fn slot(&self) -> Option<&'static str> {
+        match *self {
+            PathIndexEntry::SlotKey(slot, ..) | PathIndexEntry::Slot(slot) => Some(slot),
+            PathIndexEntry::Key(..) | PathIndexEntry::Empty => None,
+        }
+    }
+
+    /// Index within a Multi child.
+    pub fn index(&self) -> Option<usize> {
+        match *self {
+            PathIndexEntry::Key(_, index) | PathIndexEntry::SlotKey(_, _, index) => Some(index),
+            PathIndexEntry::Empty | PathIndexEntry::Slot(_) => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -17,6 +48,10 @@ pub enum PathEntry {
     Key(Key),
     /// Used for Single and Option children.
     Empty,
+    /// Used for Multi children of a named slot.
+    SlotKey(&'static str, Key),
+    /// Used for Single and Option children of a named slot.
+    Slot(&'static str),
 }
 
 impl fmt::Display for PathEntry {
@@ -24,6 +59,8 @@ impl fmt::Display for PathEntry {
         match *self {
             PathEntry::Key(ref k) => write!(f, "{}", k),
             PathEntry::Empty => Ok(()),
+            PathEntry::SlotKey(slot, ref k) => write!(f, "{}:{}", slot, k),
+            PathEntry::Slot(slot) => write!(f, "{}", slot),
         }
     }
 }
@@ -45,6 +82,30 @@ impl Path {
     pub fn iter<'a>(&'a self) -> impl Iterator<Item = &'a PathEntry> {
         self.path.iter()
     }
+
+    pub fn push(&mut self, entry: PathEntry) {
+        self.path.push(entry);
+    }
+
+    /// Returns a new path with `entry` appended.
+    pub fn join(&self, entry: PathEntry) -> Path {
+        let mut path = self.clone();
+        path.push(entry);
+        path
+    }
+
+    /// Returns the path of the parent node or `None` for the root.
+    pub fn parent(&self) -> Option<Path> {
+        if self.path.len() <= 1 {
+            return None;
+        }
+        Some(Path { path: self.path[..self.path.len() - 1].to_vec() })
+    }
+
+    /// Returns true if `self` is `other` or one of its descendants.
+    pub fn starts_with(&self, other: &Path) -> bool {
+        self.path.starts_with(&other.path)
+    }
 }
 
 impl FromIterator<PathEntry> for Path {
@@ -98,6 +159,20 @@ impl<'a> SimplePathFrame<'a> {
         }
     }
 
+    pub fn add_slot_key(&'a self, slot: &'static str, key: Key) -> SimplePathFrame<'a> {
+        SimplePathFrame {
+            parent: Some(self),
+            path_entry: PathEntry::SlotKey(slot, key),
+        }
+    }
+
+    pub fn add_slot(&'a self, slot: &'static str) -> SimplePathFrame<'a> {
+        SimplePathFrame {
+            parent: Some(self),
+            path_entry: PathEntry::Slot(slot),
+        }
+    }
+
     pub fn parent(&'a self) -> Option<&'a SimplePathFrame<'a>> {
         self.parent.as_ref().map(|pf| *pf)
     }
@@ -110,8 +185,11 @@ impl<'a> SimplePathFrame<'a> {
         SimplePathFrameIter(Some(self))
     }
 
+    /// Returns the path from the root down to this frame.
     pub fn to_path(&self) -> Path {
-        self.iter().map(|spf| spf.path_entry().clone()).collect()
+        let mut path: Vec<_> = self.iter().map(|spf| spf.path_entry().clone()).collect();
+        path.reverse();
+        Path { path: path }
     }
 }
 
@@ -160,6 +238,28 @@ impl<'a, AN> PathFrame<'a, AN> {
         }
     }
 
+    pub fn add_slot_key(
+        &'a self,
+        slot: &'static str,
+        key: Key,
+        index: usize,
+        node: &'a AN,
+    ) -> PathFrame<'a, AN> {
+        PathFrame {
+            parent: Some(self),
+            node: node,
+            path_index_entry: PathIndexEntry::SlotKey(slot, key, index),
+        }
+    }
+
+    pub fn add_slot(&'a self, slot: &'static str, node: &'a AN) -> PathFrame<'a, AN> {
+        PathFrame {
+            parent: Some(self),
+            node: node,
+            path_index_entry: PathIndexEntry::Slot(slot),
+        }
+    }
+
     pub fn parent(&'a self) -> Option<&'a PathFrame<'a, AN>> {
         self.parent.as_ref().map(|pf| *pf)
     }
@@ -173,18 +273,18 @@ impl<'a, AN> PathFrame<'a, AN> {
     }
 
     pub fn to_path_entry(&self) -> PathEntry {
-        match self.path_index_entry {
-            PathIndexEntry::Key(ref key, _) => PathEntry::Key(key.clone()),
-            PathIndexEntry::Empty => PathEntry::Empty,
-        }
+        self.path_index_entry.to_path_entry()
     }
 
     pub fn iter(&'a self) -> PathFrameIter<'a, AN> {
         PathFrameIter(Some(self))
     }
 
+    /// Returns the path from the root down to this frame.
     pub fn to_path(&self) -> Path {
-        self.iter().map(|pf| pf.to_path_entry()).collect()
+        let mut path: Vec<_> = self.iter().map(|pf| pf.to_path_entry()).collect();
+        path.reverse();
+        Path { path: path }
     }
 }
 
@@ -213,8 +313,8 @@ pub trait Differ<CTX, AN>: Debug {
         curr: &PathFrame<AN>,
         last: &PathFrame<AN>,
     ) {
-        self.diff_removed(ctx, curr);
-        self.diff_added(ctx, last);
+        self.diff_removed(ctx, last);
+        self.diff_added(ctx, curr);
     }
 
     fn diff_params_changed(
@@ -224,10 +324,18 @@ pub trait Differ<CTX, AN>: Debug {
         last: &PathFrame<AN>,
     );
 
+    /// Called for every Multi child of `parent`, `slot` being the name of the slot or `None`
+    /// for the `children` of a node without named slots.
+    ///
+    /// `indices` are `(curr_index, index)` pairs moving the child at `index` to `curr_index`,
+    /// each child moving at most once. `index` is the position after the removals, with the
+    /// added children inserted at their curr index in the order they were reported and the
+    /// kept ones in their last order, so the added ones never move.
     fn diff_reordered<I: Iterator<Item = (usize, usize)>>(
         &mut self,
         ctx: &mut Context<CTX, AN>,
         parent: &PathFrame<AN>,
+        slot: Option<&'static str>,
         indices: I,
     );
 
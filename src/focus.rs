@@ -0,0 +1,172 @@
+use diff::{Context, Differ, Path, PathFrame};
+use node::NodeChildren;
+
+/// Whether nodes using a params type can receive focus. `define_params!` implements it for
+/// every params struct, returning the `bool` field marked with `#[focusable]` or `false`.
+pub trait FocusParams {
+    fn focusable(&self) -> bool;
+}
+
+/// Implemented for `AllNodes` by `define_nodes!` by forwarding to the node's params.
+pub trait FocusNode: NodeChildren {
+    fn focusable(&self) -> bool;
+}
+
+/// Tracks the focused node by `Path` and the tab order of the focusable nodes.
+///
+/// Paths are built from the keys of `Multi` children, so focus stays on a node as long as its
+/// key does. Used as a `Differ` it notices when the focused node is removed and the following
+/// `update` moves focus to the closest focusable neighbour in the previous tab order.
+#[derive(Debug, Default)]
+pub struct Focus {
+    focused: Option<Path>,
+    order: Vec<Path>,
+    lost: bool,
+}
+
+impl Focus {
+    pub fn new() -> Focus {
+        Focus::default()
+    }
+
+    pub fn focused(&self) -> Option<&Path> {
+        self.focused.as_ref()
+    }
+
+    pub fn is_focused(&self, path: &Path) -> bool {
+        self.focused.as_ref() == Some(path)
+    }
+
+    /// Focusable nodes in tab order, as of the last `update`.
+    pub fn order(&self) -> &[Path] {
+        &self.order
+    }
+
+    /// Focuses `path` if it is a focusable node, returns whether focus was set.
+    pub fn focus(&mut self, path: &Path) -> bool {
+        if !self.order.contains(path) {
+            return false;
+        }
+        self.focused = Some(path.clone());
+        self.lost = false;
+        true
+    }
+
+    pub fn blur(&mut self) {
+        self.focused = None;
+        self.lost = false;
+    }
+
+    /// Moves focus to the next focusable node in tab order, wrapping around at the end.
+    pub fn focus_next(&mut self) -> Option<&Path> {
+        let index = match self.focused_index() {
+            Some(index) => index + 1,
+            None => 0,
+        };
+        self.focus_index(index)
+    }
+
+    /// Moves focus to the previous focusable node in tab order, wrapping around at the start.
+    pub fn focus_prev(&mut self) -> Option<&Path> {
+        let len = self.order.len();
+        let index = match self.focused_index() {
+            Some(index) => index + len - 1,
+            None => len.wrapping_sub(1),
+        };
+        self.focus_index(index)
+    }
+
+    /// Recomputes the tab order from the expanded tree `root`. Has to be called after every
+    /// diff, a focused node which was removed or isn't focusable anymore loses focus to its
+    /// closest neighbour: the next node of the previous tab order that is still focusable, or
+    /// else the previous one.
+    pub fn update<AN: FocusNode>(&mut self, root: &AN) {
+        let mut order = Vec::new();
+        collect_focusable(root, PathFrame::new(root).to_path(), &mut order);
+        let last_order = ::std::mem::replace(&mut self.order, order);
+
+        let focused = match self.focused.take() {
+            Some(focused) => focused,
+            None => return,
+        };
+        if !self.lost && self.order.contains(&focused) {
+            self.focused = Some(focused);
+            return;
+        }
+        self.lost = false;
+
+        let index = match last_order.iter().position(|p| *p == focused) {
+            Some(index) => index,
+            None => return,
+        };
+        let order = &self.order;
+        self.focused = last_order[index + 1..]
+            .iter()
+            .chain(last_order[..index].iter().rev())
+            .find(|p| order.contains(p))
+            .cloned();
+    }
+
+    fn focused_index(&self) -> Option<usize> {
+        match self.focused {
+            Some(ref focused) => self.order.iter().position(|p| p == focused),
+            None => None,
+        }
+    }
+
+    fn focus_index(&mut self, index: usize) -> Option<&Path> {
+        if self.order.is_empty() {
+            return None;
+        }
+        self.focused = Some(self.order[index % self.order.len()].clone());
+        self.lost = false;
+        self.focused.as_ref()
+    }
+}
+
+fn collect_focusable<AN: FocusNode>(node: &AN, path: Path, order: &mut Vec<Path>) {
+    if node.focusable() {
+        order.push(path.clone());
+    }
+    node.for_each_child(|entry, child| {
+        collect_focusable(child, path.join(entry.to_path_entry()), order);
+    });
+}
+
+impl<CTX, AN> Differ<CTX, AN> for Focus {
+    fn diff_added(&mut self, _ctx: &mut Context<CTX, AN>, _curr: &PathFrame<AN>) {}
+
+    fn diff_removed(&mut self, _ctx: &mut Context<CTX, AN>, last: &PathFrame<AN>) {
+        if let Some(ref focused) = self.focused {
+            if focused.starts_with(&last.to_path()) {
+                self.lost = true;
+            }
+        }
+    }
+
+    /// The replacing node keeps focus if it is focusable, which `update` checks.
+    fn diff_replaced(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        _curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+    }
+
+    fn diff_params_changed(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        _curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+    }
+
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        _parent: &PathFrame<AN>,
+        _slot: Option<&'static str>,
+        _indices: I,
+    ) {
+    }
+}
@@ -1,15 +1,19 @@
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 use std::convert::{From, Into};
 use std::borrow::Borrow;
 
-#[derive(Debug, Eq, Hash, Clone)]
+#[derive(Debug, Eq, Clone)]
 pub enum Key {
     U64(u64),
     I64(i64),
     String(Rc<String>),
     Str(&'static str),
     Bytes(Rc<Vec<u8>>),
+    /// Positional key of a child without a key, e.g. the `n`th unkeyed child in `markup!`.
+    /// Never equal to a user provided key.
+    Auto(u64),
 }
 
 impl PartialEq for Key {
@@ -22,11 +26,26 @@ impl PartialEq for Key {
             (&Key::Str(a), &Key::String(ref b)) => a == b.as_str(),
             (&Key::Str(a), &Key::Str(b)) => a == b,
             (&Key::Bytes(ref a), &Key::Bytes(ref b)) => a == b,
+            (&Key::Auto(ref a), &Key::Auto(ref b)) => a == b,
             _ => false,
         }
     }
 }
 
+/// `Key::String` and `Key::Str` hash alike as they can be equal.
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Key::U64(n) => (0u8, n).hash(state),
+            Key::I64(n) => (1u8, n).hash(state),
+            Key::String(ref s) => (2u8, s.as_str()).hash(state),
+            Key::Str(s) => (2u8, s).hash(state),
+            Key::Bytes(ref bytes) => (3u8, bytes).hash(state),
+            Key::Auto(n) => (4u8, n).hash(state),
+        }
+    }
+}
+
 macro_rules! impl_from_int_for_key {
     ($tyu:ty, $tyi:ty) => {
         impl From<$tyu> for Key {
@@ -102,6 +121,7 @@ impl fmt::Display for Key {
                 }
                 Ok(())
             }
+            Key::Auto(ref n) => write!(f, "a{}", n),
         }
     }
 }
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+use diff::{Context, Differ, Path, PathFrame};
+use node::NodeChildren;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Direction {
+    Row,
+    Column,
+}
+
+impl Default for Direction {
+    fn default() -> Direction {
+        Direction::Column
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Edges {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Edges {
+    pub fn all(v: f32) -> Edges {
+        Edges {
+            top: v,
+            right: v,
+            bottom: v,
+            left: v,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// Main axis the children are placed along.
+    pub direction: Direction,
+    /// Share of the free space on the parent's main axis this node grows into.
+    pub grow: f32,
+    /// Share of the missing space on the parent's main axis this node shrinks by, weighted by
+    /// its base size.
+    pub shrink: f32,
+    pub padding: Edges,
+    /// Fixed width, overrides the measured content width.
+    pub width: Option<f32>,
+    /// Fixed height, overrides the measured content height.
+    pub height: Option<f32>,
+    /// Hides the parts of the children outside of this node's box, so `Layout::hit_test`
+    /// doesn't look for hits in the children of a missed node.
+    pub clip: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            direction: Direction::Column,
+            grow: 0.0,
+            shrink: 1.0,
+            padding: Edges::default(),
+            width: None,
+            height: None,
+            clip: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        }
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        self.x <= x && x < self.x + self.width && self.y <= y && y < self.y + self.height
+    }
+
+    fn inset(&self, edges: &Edges) -> Rect {
+        Rect {
+            x: self.x + edges.left,
+            y: self.y + edges.top,
+            width: (self.width - edges.left - edges.right).max(0.0),
+            height: (self.height - edges.top - edges.bottom).max(0.0),
+        }
+    }
+}
+
+/// Layout style of a params type. `define_params!` implements it for every params struct,
+/// returning the field marked with `#[layout]` or the default style.
+pub trait LayoutParams {
+    fn layout_style(&self) -> Style;
+}
+
+/// Implemented for `AllNodes` by `define_nodes!` by forwarding to the node's params.
+pub trait LayoutNode: NodeChildren {
+    fn layout_style(&self) -> Style;
+}
+
+/// Computes and caches the boxes of an expanded tree by `Path`.
+///
+/// Used as a `Differ` it invalidates the changed nodes and their ancestors only, so the next
+/// `compute` reuses every untouched subtree which keeps its box.
+#[derive(Debug, Default)]
+pub struct Layout {
+    sizes: HashMap<Path, Size>,
+    rects: HashMap<Path, Rect>,
+    dirty: HashSet<Path>,
+}
+
+impl Layout {
+    pub fn new() -> Layout {
+        Layout::default()
+    }
+
+    pub fn rect(&self, path: &Path) -> Option<&Rect> {
+        self.rects.get(path)
+    }
+
+    pub fn is_dirty(&self, path: &Path) -> bool {
+        self.dirty.contains(path)
+    }
+
+    /// Marks `path` and all of its ancestors for recomputation.
+    pub fn invalidate(&mut self, path: &Path) {
+        let mut path = Some(path.clone());
+        while let Some(p) = path {
+            path = p.parent();
+            if !self.dirty.insert(p) {
+                // ancestors are already dirty
+                break;
+            }
+        }
+    }
+
+    /// Drops the cached boxes of `path` and its descendants and invalidates its ancestors.
+    pub fn forget(&mut self, path: &Path) {
+        self.sizes.retain(|p, _| !p.starts_with(path));
+        self.rects.retain(|p, _| !p.starts_with(path));
+        self.dirty.retain(|p| !p.starts_with(path));
+        if let Some(parent) = path.parent() {
+            self.invalidate(&parent);
+        }
+    }
+
+    pub fn compute<AN: LayoutNode>(&mut self, root: &AN, bounds: Rect) {
+        let path = PathFrame::new(root).to_path();
+        self.measure(root, &path);
+        self.arrange(root, &path, bounds);
+        self.dirty.clear();
+    }
+
+    /// Returns the path of the deepest node whose box contains the point. Children later in
+    /// order are placed on top of their earlier siblings and are tested first. Children
+    /// overflowing their parent's box are hit outside of it too, unless the parent has
+    /// `Style::clip` set.
+    ///
+    /// Uses the boxes of the last `compute`, so `root` has to be the tree passed to it. The
+    /// node itself can be looked up with `NodeChildren::find`, e.g. to `ParamsEvents::send` a
+    /// pointer event to it.
+    pub fn hit_test<AN: LayoutNode>(&self, root: &AN, x: f32, y: f32) -> Option<Path> {
+        self.hit_test_node(root, PathFrame::new(root).to_path(), x, y)
+    }
+
+    fn hit_test_node<AN: LayoutNode>(&self, node: &AN, path: Path, x: f32, y: f32) -> Option<Path> {
+        let contains = match self.rects.get(&path) {
+            Some(rect) => rect.contains(x, y),
+            None => return None,
+        };
+        if !contains && node.layout_style().clip {
+            return None;
+        }
+
+        let mut children = Vec::new();
+        node.for_each_child(|entry, child| children.push((entry, child)));
+        for (entry, child) in children.into_iter().rev() {
+            let hit = self.hit_test_node(child, path.join(entry.to_path_entry()), x, y);
+            if hit.is_some() {
+                return hit;
+            }
+        }
+        if contains { Some(path) } else { None }
+    }
+
+    fn measure<AN: LayoutNode>(&mut self, node: &AN, path: &Path) -> Size {
+        if !self.dirty.contains(path) {
+            if let Some(size) = self.sizes.get(path) {
+                return *size;
+            }
+        }
+
+        let style = node.layout_style();
+        let mut main = 0.0f32;
+        let mut cross = 0.0f32;
+        node.for_each_child(|entry, child| {
+            let child_path = path.join(entry.to_path_entry());
+            let size = self.measure(child, &child_path);
+            let (child_main, child_cross) = split(style.direction, size);
+            main += child_main;
+            cross = cross.max(child_cross);
+        });
+        let content = join(style.direction, main, cross);
+        let size = Size {
+            width: style.width
+                .unwrap_or(content.width + style.padding.left + style.padding.right),
+            height: style.height
+                .unwrap_or(content.height + style.padding.top + style.padding.bottom),
+        };
+        self.sizes.insert(path.clone(), size);
+        size
+    }
+
+    fn arrange<AN: LayoutNode>(&mut self, node: &AN, path: &Path, rect: Rect) {
+        if !self.dirty.contains(path) && self.rects.get(path) == Some(&rect) {
+            return;
+        }
+        self.rects.insert(path.clone(), rect);
+
+        let style = node.layout_style();
+        let inner = rect.inset(&style.padding);
+        let (inner_main, inner_cross) = split(style.direction, Size {
+            width: inner.width,
+            height: inner.height,
+        });
+
+        let mut children: Vec<(Path, &AN, Style, f32)> = Vec::new();
+        node.for_each_child(|entry, child| {
+            let child_path = path.join(entry.to_path_entry());
+            let size = self.sizes[&child_path];
+            children.push((child_path, child, child.layout_style(), split(style.direction, size).0));
+        });
+
+        let used: f32 = children.iter().map(|c| c.3).sum();
+        let free = inner_main - used;
+        let total_grow: f32 = children.iter().map(|c| c.2.grow).sum();
+        let total_shrink: f32 = children.iter().map(|c| c.2.shrink * c.3).sum();
+
+        let mut offset = 0.0;
+        for (child_path, child, child_style, basis) in children {
+            let main = if 0.0 < free && 0.0 < total_grow {
+                basis + free * child_style.grow / total_grow
+            } else if free < 0.0 && 0.0 < total_shrink {
+                (basis + free * child_style.shrink * basis / total_shrink).max(0.0)
+            } else {
+                basis
+            };
+            let fixed_cross = match style.direction {
+                Direction::Row => child_style.height,
+                Direction::Column => child_style.width,
+            };
+            let cross = fixed_cross.unwrap_or(inner_cross);
+            let child_rect = match style.direction {
+                Direction::Row => Rect::new(inner.x + offset, inner.y, main, cross),
+                Direction::Column => Rect::new(inner.x, inner.y + offset, cross, main),
+            };
+            offset += main;
+            self.arrange(child, &child_path, child_rect);
+        }
+    }
+}
+
+fn split(direction: Direction, size: Size) -> (f32, f32) {
+    match direction {
+        Direction::Row => (size.width, size.height),
+        Direction::Column => (size.height, size.width),
+    }
+}
+
+fn join(direction: Direction, main: f32, cross: f32) -> Size {
+    match direction {
+        Direction::Row => Size {
+            width: main,
+            height: cross,
+        },
+        Direction::Column => Size {
+            width: cross,
+            height: main,
+        },
+    }
+}
+
+impl<CTX, AN> Differ<CTX, AN> for Layout {
+    fn diff_added(&mut self, _ctx: &mut Context<CTX, AN>, curr: &PathFrame<AN>) {
+        self.invalidate(&curr.to_path());
+    }
+
+    fn diff_removed(&mut self, _ctx: &mut Context<CTX, AN>, last: &PathFrame<AN>) {
+        self.forget(&last.to_path());
+    }
+
+    fn diff_replaced(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+        let path = curr.to_path();
+        self.forget(&path);
+        self.invalidate(&path);
+    }
+
+    fn diff_params_changed(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+        self.invalidate(&curr.to_path());
+    }
+
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        parent: &PathFrame<AN>,
+        _slot: Option<&'static str>,
+        mut indices: I,
+    ) {
+        if indices.next().is_some() {
+            self.invalidate(&parent.to_path());
+        }
+    }
+}
@@ -8,3 +8,10 @@ pub mod key;
 pub mod child;
 pub mod node;
 pub mod widget;
+pub mod layout;
+pub mod focus;
+pub mod a11y;
+pub mod transition;
+pub mod template;
+#[cfg(feature = "test-support")]
+pub mod testing;
@@ -1,14 +1,121 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::error::Error;
 use std::any::TypeId;
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use diff::{Path, PathIndexEntry};
+use child::SlotBuilder;
 
 pub struct BuilderParams;
+/// Marker for the `children` of a node without named slots. `define_nodes!` generates a marker
+/// per named slot in `builders::slots`.
 pub struct BuilderChild;
 
 pub trait BuilderSetter<K, V> {
     fn builder_set(&mut self, value: V);
 }
 
+/// Type-state of a `#[required]` field in a `define_params!` builder which has been set. Unset
+/// fields are marked with a `<Params>Missing<Field>` type instead, the builder's `build()` is only
+/// available once all of them are `RequiredSet`.
+pub struct RequiredSet;
+
+pub trait IsRequiredSet {}
+
+impl IsRequiredSet for RequiredSet {}
+
+/// Rejection of a `#[validate]` or `#[finalize]` hook of `define_params!` params.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamsError {
+    pub params: &'static str,
+    /// The validated field, `None` for the `finalize` hook.
+    pub field: Option<&'static str>,
+    pub message: String,
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.field {
+            Some(field) => write!(f, "invalid `{}::{}`: {}", self.params, field, self.message),
+            None => write!(f, "invalid `{}`: {}", self.params, self.message),
+        }
+    }
+}
+
+impl Error for ParamsError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+/// The `#[validate]` and `#[finalize]` hooks of params, implemented by `define_params!`. The
+/// params builders, `set_params` of the node builders, the `DefaultParams` fallback and the
+/// template loading all run them, only the nodes' `new` takes params as they are.
+pub trait CheckParams: Sized {
+    fn check_params(self) -> Result<Self, ParamsError>;
+}
+
+/// Params of a node whose builder got none set. `define_params!` implements it for the params
+/// deriving `Default` without `#[required]` fields, other params types have to be set on the node
+/// builders.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no default params, the params of the node have to be set",
+    label = "params not set",
+    note = "set them with `set_params` or `params()`, or derive `Default` on `{Self}` if none of its fields are `#[required]`"
+)]
+pub trait DefaultParams: Sized {
+    fn default_params() -> Self;
+}
+
+/// `DefaultParams` of `P` if it implements them, for code generic over any params type like the
+/// template loading.
+pub fn default_params<P>() -> Option<P> {
+    <P as MaybeDefaultParams>::maybe_default_params()
+}
+
+trait MaybeDefaultParams: Sized {
+    fn maybe_default_params() -> Option<Self>;
+}
+
+impl<T> MaybeDefaultParams for T {
+    default fn maybe_default_params() -> Option<T> {
+        None
+    }
+}
+
+impl<T: DefaultParams> MaybeDefaultParams for T {
+    fn maybe_default_params() -> Option<T> {
+        Some(T::default_params())
+    }
+}
+
+/// Type-state of a node builder whose params haven't been set.
+pub struct ParamsUnset;
+
+/// Type-state of a node builder whose params have been set.
+pub struct ParamsSet;
+
+/// Params of a node builder in the type-state `Self` when it's built, the builder of a node with
+/// params of type `P` only builds without set params if `P` implements `DefaultParams`.
+pub trait BuilderParamsState<P> {
+    fn params(params: Option<P>) -> P;
+}
+
+impl<P> BuilderParamsState<P> for ParamsSet {
+    fn params(params: Option<P>) -> P {
+        params.expect("params of a builder in the `ParamsSet` state")
+    }
+}
+
+impl<P: DefaultParams + CheckParams> BuilderParamsState<P> for ParamsUnset {
+    fn params(_: Option<P>) -> P {
+        match P::default_params().check_params() {
+            Ok(params) => params,
+            Err(err) => panic!("default params rejected: {}", err),
+        }
+    }
+}
+
 pub trait Params<PB>
     where PB: BuilderSetter<BuilderParams, Self>,
           Self: Sized,
@@ -18,6 +125,71 @@ pub trait Params<PB>
     fn builder(parent_builder: PB) -> Self::Builder;
 }
 
+/// Gives generic code access to the direct children of a node. Implemented for `AllNodes` by
+/// `define_nodes!`.
+pub trait NodeChildren {
+    /// Calls `f` for every direct child in order.
+    fn for_each_child<'a, F>(&'a self, f: F)
+        where F: FnMut(PathIndexEntry, &'a Self);
+
+    /// Looks up the node at `path`, with `self` being the root of the path.
+    fn find<'a>(&'a self, path: &Path) -> Option<&'a Self>
+        where Self: Sized
+    {
+        let mut node = Some(self);
+        for entry in path.iter().skip(1) {
+            let parent = match node {
+                Some(parent) => parent,
+                None => return None,
+            };
+            node = None;
+            parent.for_each_child(|child_entry, child| {
+                if node.is_none() && child_entry.to_path_entry() == *entry {
+                    node = Some(child);
+                }
+            });
+        }
+        node
+    }
+}
+
+/// Schema of the `children` slot of a node, implemented by `define_nodes!` for every node
+/// having one. `Group` is the group, or node for a slot of a single node type, it accepts.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no `children` slot",
+    label = "can't have children"
+)]
+pub trait ChildrenGroup {
+    type Group;
+}
+
+/// Implemented by `define_nodes!` for every node allowed in a slot of `P`, with `G` being the
+/// group the slot accepts. Only used to type-check the nesting in `markup!`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not allowed as a child of `{P}` (expected group `{G}`)",
+    label = "not allowed in `{P}`"
+)]
+pub trait ChildOf<P, G> {}
+
+/// Compiles only if `C` is allowed as a child of `P`, `markup!` calls it for every node child.
+#[inline]
+pub fn check_child<P: ChildrenGroup, C: ChildOf<P, P::Group>>() {}
+
+/// Nesting check of the named slot of `P` whose builder `slot` returns, `markup!` calls
+/// `check_slot::<P, _>(slot).child::<C>()` for every node child of a named slot.
+#[inline]
+pub fn check_slot<P, B: SlotBuilder>(_slot: fn() -> B) -> SlotCheck<P, B::Group> {
+    SlotCheck(PhantomData)
+}
+
+pub struct SlotCheck<P, G>(PhantomData<(P, G)>);
+
+impl<P, G> SlotCheck<P, G> {
+    /// Compiles only if `C` is allowed in the slot.
+    #[inline]
+    pub fn child<C: ChildOf<P, G>>(&self) {}
+}
+
 pub trait ParamsEvents<EA>: Debug {
     fn has(&self, event_name: &str) -> bool;
     fn send(&mut self, event_name: &str, event: EA);
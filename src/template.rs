@@ -0,0 +1,980 @@
+//! Runtime version of the `markup!` syntax, for trees loaded from files.
+//!
+//! A template holds a single root node written like in `markup!`, e.g.
+//!
+//! ```text
+//! // comments run to the end of the line
+//! Dialog title="Settings" {
+//!     Button@"ok" label="Ok" default? /
+//!     Label "Text"
+//!     footer: {
+//!         Label "Named slots hold their children in a block"
+//!     }
+//! }
+//! ```
+//!
+//! Params values are string, char, integer, float and bool literals, keys strings, chars and
+//! integers. Expressions, widgets and control flow aren't supported. `define_nodes!` implements
+//! `FromTemplate` for the nodes and groups and `define_params!` implements `TemplateParams`,
+//! which sets the params fields by name through `FromValue`. `TemplateSchema` lists the nodes
+//! and the children they accept.
+//!
+//! The other way around `Markup` prints a tree in this syntax, which `markup!` accepts as well.
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{self, Debug};
+use diff::{Context, Differ, PathFrame, PathIndexEntry, SimplePathFrame};
+use key::Key;
+use node::{self, NodeChildren};
+
+/// Line and column of a template node or param, both starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateError {
+    pub message: String,
+    pub position: Position,
+}
+
+impl TemplateError {
+    pub fn new<S: Into<String>>(message: S, position: Position) -> TemplateError {
+        TemplateError {
+            message: message.into(),
+            position: position,
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.message)
+    }
+}
+
+impl Error for TemplateError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Char(char),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::String(ref v) => write!(f, "{:?}", v),
+            Value::Char(v) => write!(f, "{:?}", v),
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{:?}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// `name=value` or `name?`, the latter having the value `true`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub value: Value,
+    pub position: Position,
+}
+
+/// `name: { .. }` or `name: Child` within the children of a node, holding the children of its
+/// named slot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slot {
+    pub name: String,
+    pub children: Vec<Node>,
+    pub position: Position,
+}
+
+impl Slot {
+    pub fn error<S: Into<String>>(&self, message: S) -> TemplateError {
+        TemplateError::new(message, self.position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Node {
+        name: String,
+        key: Option<Key>,
+        params: Vec<Param>,
+        children: Vec<Node>,
+        /// Named slots in the order they are written.
+        slots: Vec<Slot>,
+        position: Position,
+    },
+    Text {
+        text: String,
+        key: Option<Key>,
+        position: Position,
+    },
+}
+
+impl Node {
+    /// Name of the node, `None` for text.
+    pub fn name(&self) -> Option<&str> {
+        match *self {
+            Node::Node { ref name, .. } => Some(name),
+            Node::Text { .. } => None,
+        }
+    }
+
+    pub fn key(&self) -> Option<&Key> {
+        match *self {
+            Node::Node { ref key, .. } | Node::Text { ref key, .. } => key.as_ref(),
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        match *self {
+            Node::Node { position, .. } | Node::Text { position, .. } => position,
+        }
+    }
+
+    pub fn params(&self) -> &[Param] {
+        match *self {
+            Node::Node { ref params, .. } => params,
+            Node::Text { .. } => &[],
+        }
+    }
+
+    pub fn children(&self) -> &[Node] {
+        match *self {
+            Node::Node { ref children, .. } => children,
+            Node::Text { .. } => &[],
+        }
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        match *self {
+            Node::Node { ref slots, .. } => slots,
+            Node::Text { .. } => &[],
+        }
+    }
+
+    /// Children of the named slot `name`, empty if it isn't written.
+    pub fn slot_children(&self, name: &str) -> &[Node] {
+        self.slots()
+            .iter()
+            .find(|slot| slot.name == name)
+            .map_or(&[], |slot| &slot.children[..])
+    }
+
+    pub fn error<S: Into<String>>(&self, message: S) -> TemplateError {
+        TemplateError::new(message, self.position())
+    }
+
+    /// The children paired with their keys, unkeyed children get a `Key::Auto` with their index
+    /// among the unkeyed ones like in `markup!`.
+    pub fn keyed_children<'a>(&'a self) -> impl Iterator<Item = (Key, &'a Node)> + 'a {
+        keyed(self.children())
+    }
+
+    /// `keyed_children` of the named slot `name`.
+    pub fn keyed_slot_children<'a>(&'a self, name: &str)
+                                   -> impl Iterator<Item = (Key, &'a Node)> + 'a {
+        keyed(self.slot_children(name))
+    }
+}
+
+fn keyed<'a>(children: &'a [Node]) -> impl Iterator<Item = (Key, &'a Node)> + 'a {
+    let mut index = 0;
+    children.iter().map(move |child| {
+        let key = match child.key() {
+            Some(key) => key.clone(),
+            None => {
+                index += 1;
+                Key::Auto(index - 1)
+            }
+        };
+        (key, child)
+    })
+}
+
+/// Conversion of a param value into a params field. Types without their own implementation
+/// can't be set from templates, any value is rejected for them.
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, String>;
+
+    /// A few values accepted by `from_value`, used to generate templates.
+    fn sample_values() -> Vec<Value> {
+        Vec::new()
+    }
+}
+
+impl<T> FromValue for T {
+    default fn from_value(value: &Value) -> Result<T, String> {
+        Err(format!("can't be set from a template, got `{}`", value))
+    }
+
+    default fn sample_values() -> Vec<Value> {
+        Vec::new()
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<String, String> {
+        match *value {
+            Value::String(ref v) => Ok(v.clone()),
+            Value::Char(v) => Ok(v.to_string()),
+            ref value => Err(format!("expected a string, got `{}`", value)),
+        }
+    }
+
+    fn sample_values() -> Vec<Value> {
+        vec![Value::String(String::new()), Value::String("a".into()), Value::Char('b')]
+    }
+}
+
+impl FromValue for Cow<'static, str> {
+    fn from_value(value: &Value) -> Result<Cow<'static, str>, String> {
+        String::from_value(value).map(Cow::Owned)
+    }
+
+    fn sample_values() -> Vec<Value> {
+        String::sample_values()
+    }
+}
+
+impl FromValue for char {
+    fn from_value(value: &Value) -> Result<char, String> {
+        match *value {
+            Value::Char(v) => Ok(v),
+            ref value => Err(format!("expected a char, got `{}`", value)),
+        }
+    }
+
+    fn sample_values() -> Vec<Value> {
+        vec![Value::Char('a'), Value::Char('b')]
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<bool, String> {
+        match *value {
+            Value::Bool(v) => Ok(v),
+            ref value => Err(format!("expected a bool, got `{}`", value)),
+        }
+    }
+
+    fn sample_values() -> Vec<Value> {
+        vec![Value::Bool(false), Value::Bool(true)]
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Option<T>, String> {
+        T::from_value(value).map(Some)
+    }
+
+    fn sample_values() -> Vec<Value> {
+        T::sample_values()
+    }
+}
+
+macro_rules! impl_from_value_for_int {
+    ($($ty:ident),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: &Value) -> Result<$ty, String> {
+                    match *value {
+                        Value::Int(v) if v >= $ty::min_value() as i64 &&
+                                         (v < 0 || v as u64 <= $ty::max_value() as u64) => {
+                            Ok(v as $ty)
+                        }
+                        Value::Int(v) => {
+                            Err(format!("{} is out of range for `{}`", v, stringify!($ty)))
+                        }
+                        ref value => Err(format!("expected an integer, got `{}`", value)),
+                    }
+                }
+
+                fn sample_values() -> Vec<Value> {
+                    vec![Value::Int(0), Value::Int(1), Value::Int(-1)]
+                        .into_iter()
+                        .filter(|value| $ty::from_value(value).is_ok())
+                        .collect()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value_for_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+macro_rules! impl_from_value_for_float {
+    ($($ty:ident),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: &Value) -> Result<$ty, String> {
+                    match *value {
+                        Value::Float(v) => Ok(v as $ty),
+                        Value::Int(v) => Ok(v as $ty),
+                        ref value => Err(format!("expected a number, got `{}`", value)),
+                    }
+                }
+
+                fn sample_values() -> Vec<Value> {
+                    vec![Value::Float(0.5), Value::Int(2)]
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value_for_float!(f32, f64);
+
+/// Params whose fields can be set by name from template values, implemented by
+/// `define_params!`. Event fields can't be set.
+pub trait TemplateParams {
+    fn set_template_param(&mut self, name: &str, value: &Value) -> Result<(), String>;
+
+    /// Params with the fields set by the params of `node`, the others filled like the params
+    /// builder does. Fails for a `#[required]` field `node` doesn't set.
+    fn from_template_params(node: &Node) -> Result<Self, TemplateError> where Self: Sized;
+
+    /// The fields that can be set, with a few values for each.
+    fn template_param_schema() -> Vec<ParamSchema> where Self: Sized;
+}
+
+/// Construction from a template node, implemented by `define_nodes!` for every node and group.
+/// Nodes check the name of the template node, groups dispatch on it.
+pub trait FromTemplate: Sized {
+    fn from_template(node: &Node) -> Result<Self, TemplateError>;
+}
+
+impl FromTemplate for Cow<'static, str> {
+    fn from_template(node: &Node) -> Result<Cow<'static, str>, TemplateError> {
+        match *node {
+            Node::Text { ref text, .. } => Ok(Cow::Owned(text.clone())),
+            Node::Node { ref name, .. } => {
+                Err(node.error(format!("expected text, got the node `{}`", name)))
+            }
+        }
+    }
+}
+
+/// How many children a `children` slot holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildrenKind {
+    Single,
+    Optional,
+    Multi,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChildrenSchema {
+    pub kind: ChildrenKind,
+    /// Names of the nodes allowed in the slot.
+    pub nodes: Vec<&'static str>,
+    /// Whether text is allowed in the slot.
+    pub text: bool,
+}
+
+/// A params field as far as templates are concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamSchema {
+    pub name: &'static str,
+    /// Values accepted by the field, empty if it can't be set from a template.
+    pub values: Vec<Value>,
+    /// Whether templates have to set the field.
+    pub required: bool,
+}
+
+/// A node as far as templates are concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeSchema {
+    pub name: &'static str,
+    /// Fields of the params, empty for nodes without params.
+    pub params: Vec<ParamSchema>,
+    /// `None` for nodes without a `children` slot.
+    pub children: Option<ChildrenSchema>,
+    /// The named slots in the order they are declared.
+    pub slots: Vec<SlotSchema>,
+}
+
+/// A named slot as far as templates are concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotSchema {
+    pub name: &'static str,
+    pub children: ChildrenSchema,
+}
+
+/// The nodes of a tree and their children, implemented for `AllNodes` by `define_nodes!`.
+pub trait TemplateSchema {
+    fn template_schema() -> Vec<NodeSchema>;
+}
+
+/// Root of a reloadable tree, implemented for `AllNodes` by `define_nodes!` by forwarding to its
+/// `expand_widgets` and `diff`.
+pub trait TemplateRoot: FromTemplate {
+    fn expand_widgets(curr: &mut Self, last: Option<&Self>, path: &SimplePathFrame);
+
+    fn diff<CTX, D>(
+        curr: &PathFrame<Self>,
+        last: &PathFrame<Self>,
+        ctx: &mut Context<CTX, Self>,
+        differ: &mut D,
+    ) where D: Differ<CTX, Self>;
+}
+
+/// Keeps the tree last loaded from a template, so reloading the changed source only reports
+/// the changed nodes to the `Differ`. Independent of how changes are noticed, e.g. a file
+/// watcher calls `reload` with the new file contents.
+#[derive(Debug)]
+pub struct Reloader<AN> {
+    last: Option<AN>,
+}
+
+impl<AN: TemplateRoot> Reloader<AN> {
+    pub fn new() -> Reloader<AN> {
+        Reloader { last: None }
+    }
+
+    /// The tree of the last successful load.
+    pub fn current(&self) -> Option<&AN> {
+        self.last.as_ref()
+    }
+
+    /// Parses `source` into a new tree, expands its widgets and diffs it against the last one.
+    /// The first load reports the whole tree as added. On errors the last tree is kept and
+    /// nothing is reported, so a half-edited file doesn't tear down the view.
+    pub fn reload<CTX, D>(
+        &mut self,
+        source: &str,
+        ctx: &mut Context<CTX, AN>,
+        differ: &mut D,
+    ) -> Result<&AN, TemplateError>
+        where D: Differ<CTX, AN>
+    {
+        let mut curr: AN = from_str(source)?;
+        AN::expand_widgets(&mut curr, self.last.as_ref(), &SimplePathFrame::new());
+        match self.last {
+            Some(ref last) => {
+                AN::diff(&PathFrame::new(&curr), &PathFrame::new(last), ctx, differ);
+            }
+            None => differ.diff_added(ctx, &PathFrame::new(&curr)),
+        }
+        self.last = Some(curr);
+        Ok(self.last.as_ref().unwrap())
+    }
+}
+
+/// A params field printed as markup value, `None` leaves the field out. Strings, chars, numbers
+/// and bools are printed as literals, other `Debug` types as `(Debug output)` and types
+/// without `Debug` are left out, as are `None` options.
+pub trait ToMarkupValue {
+    fn to_markup_value(&self) -> Option<String>;
+}
+
+impl<T> ToMarkupValue for T {
+    default fn to_markup_value(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<T: Debug> ToMarkupValue for T {
+    default fn to_markup_value(&self) -> Option<String> {
+        Some(format!("({:?})", self))
+    }
+}
+
+impl<T: Debug> ToMarkupValue for Option<T> {
+    fn to_markup_value(&self) -> Option<String> {
+        self.as_ref().and_then(ToMarkupValue::to_markup_value)
+    }
+}
+
+macro_rules! impl_to_markup_value_for_literal {
+    ($($ty:ty),*) => {
+        $(
+            impl ToMarkupValue for $ty {
+                fn to_markup_value(&self) -> Option<String> {
+                    Some(format!("{:?}", self))
+                }
+            }
+        )*
+    };
+}
+
+impl_to_markup_value_for_literal!(String, char, bool, u8, u16, u32, u64, usize, i8, i16, i32, i64,
+                                  isize, f32, f64);
+
+impl<'a> ToMarkupValue for &'a str {
+    fn to_markup_value(&self) -> Option<String> {
+        Some(format!("{:?}", self))
+    }
+}
+
+impl<'a> ToMarkupValue for Cow<'a, str> {
+    fn to_markup_value(&self) -> Option<String> {
+        Some(format!("{:?}", self))
+    }
+}
+
+/// Params printable as `k=v`, implemented by `define_params!` for all fields except events.
+pub trait MarkupParams {
+    fn markup_params(&self) -> Vec<(&'static str, String)>;
+}
+
+/// `params.markup_params()` without the fields printed the same as in the params' `Default`,
+/// all of them if it has none.
+pub fn changed_markup_params<P: MarkupParams>(params: &P) -> Vec<(&'static str, String)> {
+    let mut printed = params.markup_params();
+    if let Some(default) = node::default_params::<P>() {
+        let defaults = default.markup_params();
+        printed.retain(|param| !defaults.contains(param));
+    }
+    printed
+}
+
+/// What `Markup` prints for a node, apart from its key and children.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkupHead<'a> {
+    Node {
+        name: &'static str,
+        params: Vec<(&'static str, String)>,
+    },
+    Text(&'a str),
+    /// A widget which hasn't been expanded yet.
+    Widget,
+}
+
+/// Implemented for `AllNodes` by `define_nodes!`.
+pub trait MarkupNode: NodeChildren + Sized {
+    fn markup_head(&self) -> MarkupHead;
+}
+
+/// `key` as written in markup, `None` for `Key::Auto` which is positional.
+fn markup_key(key: &Key) -> Option<String> {
+    match *key {
+        Key::U64(n) => Some(n.to_string()),
+        Key::I64(n) if n < 0 => Some(n.to_string()),
+        Key::I64(n) => Some(format!("{}i64", n)),
+        Key::String(ref s) => Some(format!("{:?}", s)),
+        Key::Str(s) => Some(format!("{:?}", s)),
+        Key::Bytes(ref bytes) => Some(format!("(vec!{:?})", bytes)),
+        Key::Auto(_) => None,
+    }
+}
+
+/// Displays a tree in markup syntax with children indented by four spaces, e.g.
+///
+/// ```text
+/// List@"list" title="Todo" {
+///     Item@1 done=true /
+///     Label {
+///         "text"
+///     }
+///     footer: {
+///         Label {
+///             "done"
+///         }
+///     }
+/// }
+/// ```
+///
+/// Children of named slots are printed in a `slot_name: { .. }` block. Unexpanded widgets are
+/// printed as `// widget` comment.
+pub struct Markup<'a, AN: 'a>(pub &'a AN);
+
+impl<'a, AN: MarkupNode> fmt::Display for Markup<'a, AN> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_markup(self.0, None, 0, f)
+    }
+}
+
+fn write_markup<AN: MarkupNode>(node: &AN, key: Option<&Key>, depth: usize,
+                                f: &mut fmt::Formatter) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    let key = key.and_then(markup_key).map(|key| format!("@{}", key)).unwrap_or_default();
+    let (name, params) = match node.markup_head() {
+        MarkupHead::Node { name, params } => (name, params),
+        MarkupHead::Text(text) => return writeln!(f, "{}{:?}{}", indent, text, key),
+        MarkupHead::Widget => return writeln!(f, "{}// widget", indent),
+    };
+    write!(f, "{}{}{}", indent, name, key)?;
+    for (name, value) in params {
+        write!(f, " {}={}", name, value)?;
+    }
+
+    let mut children = Vec::new();
+    node.for_each_child(|entry, child| children.push((entry, child)));
+    if children.is_empty() {
+        return writeln!(f, " /");
+    }
+    writeln!(f, " {{")?;
+    let mut slot = None;
+    for (entry, child) in children {
+        if entry.slot() != slot {
+            if slot.is_some() {
+                writeln!(f, "{}    }}", indent)?;
+            }
+            slot = entry.slot();
+            if let Some(slot) = slot {
+                writeln!(f, "{}    {}: {{", indent, slot)?;
+            }
+        }
+        let key = match entry {
+            PathIndexEntry::Key(ref key, _) | PathIndexEntry::SlotKey(_, ref key, _) => Some(key),
+            PathIndexEntry::Empty | PathIndexEntry::Slot(_) => None,
+        };
+        let child_depth = if slot.is_some() { depth + 2 } else { depth + 1 };
+        write_markup(child, key, child_depth, f)?;
+    }
+    if slot.is_some() {
+        writeln!(f, "{}    }}", indent)?;
+    }
+    writeln!(f, "{}}}", indent)
+}
+
+/// Parses `source` and constructs its root node, usually as `AllNodes`.
+pub fn from_str<T: FromTemplate>(source: &str) -> Result<T, TemplateError> {
+    parse(source).and_then(|node| T::from_template(&node))
+}
+
+/// Parses `source` holding a single root node.
+pub fn parse(source: &str) -> Result<Node, TemplateError> {
+    let mut parser = Parser {
+        rest: source,
+        position: Position { line: 1, column: 1 },
+    };
+    let node = parser.node()?;
+    parser.skip_whitespace();
+    if !parser.rest.is_empty() {
+        return Err(parser.error("unexpected input after the root node"));
+    }
+    Ok(node)
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+    position: Position,
+}
+
+impl<'a> Parser<'a> {
+    fn error<S: Into<String>>(&self, message: S) -> TemplateError {
+        TemplateError::new(message, self.position)
+    }
+
+    /// Error for unexpected input, naming what was expected.
+    fn expected(&self, what: &str) -> TemplateError {
+        match self.peek() {
+            Some(c) => self.error(format!("expected {}, found `{}`", what, c)),
+            None => self.error(format!("unexpected end of input, expected {}", what)),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek();
+        if let Some(c) = c {
+            self.rest = &self.rest[c.len_utf8()..];
+            if c == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            if self.rest.starts_with("//") {
+                while self.peek().map_or(false, |c| c != '\n') {
+                    self.bump();
+                }
+            } else if self.peek().map_or(false, char::is_whitespace) {
+                self.bump();
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Skips whitespace and consumes `c` if it comes next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return None,
+        }
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if !c.is_alphanumeric() && c != '_' {
+                break;
+            }
+            ident.push(c);
+            self.bump();
+        }
+        Some(ident)
+    }
+
+    /// Whether `name:` follows, starting a named slot.
+    fn at_slot(&self) -> bool {
+        let rest = self.rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_');
+        rest.len() != self.rest.len() && rest.trim_start().starts_with(':')
+    }
+
+    /// Whether an identifier follows which isn't a param, so is a single child node.
+    fn at_child_node(&self) -> bool {
+        let rest = self.rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_');
+        rest.len() != self.rest.len() &&
+        !rest.trim_start().starts_with('=') && !rest.trim_start().starts_with('?')
+    }
+
+    fn node(&mut self) -> Result<Node, TemplateError> {
+        self.skip_whitespace();
+        let position = self.position;
+        if self.peek() == Some('"') {
+            let text = self.string()?;
+            let key = self.key()?;
+            return Ok(Node::Text {
+                text: text,
+                key: key,
+                position: position,
+            });
+        }
+        let name = match self.ident() {
+            Some(name) => name,
+            None => return Err(self.expected("a node or text")),
+        };
+        let key = self.key()?;
+
+        let mut params = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.at_child_node() {
+                break;
+            }
+            let position = self.position;
+            let name = match self.ident() {
+                Some(name) => name,
+                None => break,
+            };
+            // not a child node, so `?` or `=` follows
+            let value = if self.eat('?') {
+                Value::Bool(true)
+            } else {
+                self.eat('=');
+                self.value()?
+            };
+            params.push(Param {
+                name: name,
+                value: value,
+                position: position,
+            });
+        }
+
+        let mut slots: Vec<Slot> = Vec::new();
+        let children = if self.eat('/') {
+            vec![]
+        } else if self.eat('{') {
+            let mut children = Vec::new();
+            while !self.eat('}') {
+                if self.rest.trim().is_empty() {
+                    return Err(self.error(format!("unclosed `{{` of `{}`", name)));
+                }
+                if self.at_slot() {
+                    let slot = self.slot()?;
+                    if slots.iter().any(|other| other.name == slot.name) {
+                        return Err(slot.error(format!("duplicate slot `{}`", slot.name)));
+                    }
+                    slots.push(slot);
+                } else {
+                    children.push(self.node()?);
+                }
+            }
+            children
+        } else if self.peek() == Some('"') || self.at_child_node() {
+            vec![self.node()?]
+        } else {
+            return Err(self.expected(&format!("`/`, `{{` or a child after `{}`", name)));
+        };
+
+        Ok(Node::Node {
+            name: name,
+            key: key,
+            params: params,
+            children: children,
+            slots: slots,
+            position: position,
+        })
+    }
+
+    /// `name: { .. }` or `name: Child`, at `at_slot`.
+    fn slot(&mut self) -> Result<Slot, TemplateError> {
+        let position = self.position;
+        let name = self.ident().unwrap();
+        self.eat(':');
+        let children = if self.eat('{') {
+            let mut children = Vec::new();
+            while !self.eat('}') {
+                if self.rest.trim().is_empty() {
+                    return Err(self.error(format!("unclosed `{{` of slot `{}`", name)));
+                }
+                children.push(self.node()?);
+            }
+            children
+        } else {
+            vec![self.node()?]
+        };
+        Ok(Slot {
+            name: name,
+            children: children,
+            position: position,
+        })
+    }
+
+    /// `@key`, integer keys become a `Key::U64` or for negative ones a `Key::I64` and chars a
+    /// `Key::String` like in `markup!`.
+    fn key(&mut self) -> Result<Option<Key>, TemplateError> {
+        if !self.eat('@') {
+            return Ok(None);
+        }
+        self.skip_whitespace();
+        match self.value()? {
+            Value::String(v) => Ok(Some(Key::from(v))),
+            Value::Char(v) => Ok(Some(Key::from(v.to_string()))),
+            Value::Int(v) if v < 0 => Ok(Some(Key::I64(v))),
+            Value::Int(v) => Ok(Some(Key::U64(v as u64))),
+            value => Err(self.error(format!("`{}` can't be used as a key", value))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, TemplateError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.string().map(Value::String),
+            Some('\'') => self.char(),
+            Some(c) if c == '-' || c.is_digit(10) => self.number(),
+            _ => {
+                let position = self.position;
+                match self.ident() {
+                    Some(ref v) if v == "true" => Ok(Value::Bool(true)),
+                    Some(ref v) if v == "false" => Ok(Value::Bool(false)),
+                    Some(v) => {
+                        Err(TemplateError::new(format!("expected a value, found `{}`", v),
+                                               position))
+                    }
+                    None => Err(self.expected("a value")),
+                }
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, TemplateError> {
+        let position = self.position;
+        let mut number = String::new();
+        if self.peek() == Some('-') {
+            number.push('-');
+            self.bump();
+        }
+        let mut float = false;
+        while let Some(c) = self.peek() {
+            match c {
+                c if c.is_digit(10) || c == '_' => {}
+                '.' | 'e' | 'E' => float = true,
+                '+' | '-' if number.ends_with('e') || number.ends_with('E') => {}
+                _ => break,
+            }
+            if c != '_' {
+                number.push(c);
+            }
+            self.bump();
+        }
+        let invalid = || TemplateError::new(format!("invalid number `{}`", number), position);
+        if float {
+            number.parse().map(Value::Float).map_err(|_| invalid())
+        } else {
+            number.parse().map(Value::Int).map_err(|_| invalid())
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, TemplateError> {
+        match self.bump() {
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some('0') => Ok('\0'),
+            Some(c @ '\\') | Some(c @ '"') | Some(c @ '\'') => Ok(c),
+            Some('u') if self.eat('{') => {
+                let mut hex = String::new();
+                while let Some(c) = self.bump() {
+                    if c == '}' {
+                        break;
+                    }
+                    hex.push(c);
+                }
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(::std::char::from_u32)
+                    .ok_or_else(|| self.error(format!("invalid unicode escape `\\u{{{}}}`", hex)))
+            }
+            _ => Err(self.error("unknown escape")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, TemplateError> {
+        let start = self.position;
+        self.bump();
+        let mut string = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(self.escape()?),
+                Some(c) => string.push(c),
+                None => return Err(TemplateError::new("unterminated string", start)),
+            }
+        }
+    }
+
+    fn char(&mut self) -> Result<Value, TemplateError> {
+        let start = self.position;
+        self.bump();
+        let c = match self.bump() {
+            Some('\\') => self.escape()?,
+            Some(c) => c,
+            None => return Err(TemplateError::new("unterminated char", start)),
+        };
+        match self.bump() {
+            Some('\'') => Ok(Value::Char(c)),
+            _ => Err(TemplateError::new("unterminated char", start)),
+        }
+    }
+}
@@ -0,0 +1,913 @@
+//! Snapshot and property testing of trees and diffs, enabled with the `test-support` feature.
+//!
+//! Trees are recorded in their `Markup` form after expanding their widgets, diffs as the
+//! ordered `Differ` callbacks with their paths, one per line:
+//!
+//! ```text
+//! params_changed <root>
+//! removed a0
+//! reordered <root> children [(1, 0)]
+//! ```
+//!
+//! `assert_snapshot` compares them against a stored file. Missing snapshot files are written,
+//! as are all of them while the `VTREE_UPDATE_SNAPSHOTS` environment variable is set.
+//!
+//! For fuzzing the diff `arbitrary_keys` and `arbitrary_tree_pair` generate random `Multi` key
+//! sets and trees following the `TemplateSchema` of `define_nodes!`, each with a mutated copy
+//! of it. `check_diff` and `check_multi_diff` replay the diff of such a pair on last and
+//! compare the result with curr, checking that reorders are permutations and that no key is
+//! used twice along the way. `check_property` runs a property for a range of seeds:
+//!
+//! ```ignore
+//! check_property(200, |rng| {
+//!     let (curr, last) = arbitrary_tree_pair::<AllNodes>(rng);
+//!     check_diff(&curr, &last)
+//! });
+//! ```
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::{self, Write as FmtWrite};
+use std::fs;
+use std::io::{Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path as FsPath;
+use std::rc::Rc;
+use child::Multi;
+use diff::{Context, Differ, Path, PathEntry, PathFrame, SimplePathFrame};
+use key::Key;
+use node::NodeChildren;
+use template::{self, ChildrenKind, ChildrenSchema, FromTemplate, Markup, MarkupHead, MarkupNode,
+               NodeSchema, Position, TemplateRoot, TemplateSchema};
+
+/// Environment variable which makes `assert_snapshot` overwrite the stored snapshots.
+pub const UPDATE_SNAPSHOTS_VAR: &'static str = "VTREE_UPDATE_SNAPSHOTS";
+
+/// Environment variable making `check_property` run only the given seed.
+pub const SEED_VAR: &'static str = "VTREE_SEED";
+
+fn path_str(path: &Path) -> String {
+    if path.len() <= 1 {
+        "<root>".to_string()
+    } else {
+        path.to_string().trim_start_matches('.').to_string()
+    }
+}
+
+/// `Differ` recording its callbacks, `diff_replaced` is recorded as such and not as the
+/// removal and addition it defaults to.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    pub log: Vec<String>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    /// The recorded callbacks, one per line.
+    pub fn snapshot(&self) -> String {
+        let mut snapshot = String::new();
+        for line in &self.log {
+            snapshot.push_str(line);
+            snapshot.push('\n');
+        }
+        snapshot
+    }
+}
+
+impl<CTX, AN> Differ<CTX, AN> for Recorder {
+    fn diff_added(&mut self, _ctx: &mut Context<CTX, AN>, curr: &PathFrame<AN>) {
+        self.log.push(format!("added {}", path_str(&curr.to_path())));
+    }
+
+    fn diff_removed(&mut self, _ctx: &mut Context<CTX, AN>, last: &PathFrame<AN>) {
+        self.log.push(format!("removed {}", path_str(&last.to_path())));
+    }
+
+    fn diff_replaced(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+        self.log.push(format!("replaced {}", path_str(&curr.to_path())));
+    }
+
+    fn diff_params_changed(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+        self.log.push(format!("params_changed {}", path_str(&curr.to_path())));
+    }
+
+    /// Only reorders actually moving a child are recorded.
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        parent: &PathFrame<AN>,
+        slot: Option<&'static str>,
+        indices: I,
+    ) {
+        let indices: Vec<_> = indices.collect();
+        if indices.is_empty() {
+            return;
+        }
+        self.log.push(format!("reordered {} {} {:?}",
+                              path_str(&parent.to_path()),
+                              slot.unwrap_or("children"),
+                              indices));
+    }
+}
+
+fn expanded<AN: TemplateRoot + Clone>(tree: &AN) -> AN {
+    let mut tree = tree.clone();
+    AN::expand_widgets(&mut tree, None, &SimplePathFrame::new());
+    tree
+}
+
+/// `Markup` of `tree` with its widgets expanded.
+pub fn tree_snapshot<AN: MarkupNode + TemplateRoot + Clone>(tree: &AN) -> String {
+    Markup(&expanded(tree)).to_string()
+}
+
+/// The `Differ` callbacks of diffing `curr` against `last`, both with their widgets expanded.
+pub fn diff_snapshot<AN: TemplateRoot + Clone>(curr: &AN, last: &AN) -> String {
+    let (curr, last) = (expanded(curr), expanded(last));
+    let mut recorder = Recorder::new();
+    AN::diff(&PathFrame::new(&curr),
+             &PathFrame::new(&last),
+             &mut Context::new(()),
+             &mut recorder);
+    recorder.snapshot()
+}
+
+/// Line diff of `expected` and `actual`, with removed lines prefixed by `-`, added ones by `+`
+/// and the common ones by a space.
+pub fn text_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+
+    // lengths of the longest common subsequences of the suffixes
+    let mut lcs = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            writeln!(diff, " {}", expected[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || lcs[i][j + 1] > lcs[i + 1][j]) {
+            writeln!(diff, "+{}", actual[j]).unwrap();
+            j += 1;
+        } else {
+            writeln!(diff, "-{}", expected[i]).unwrap();
+            i += 1;
+        }
+    }
+    diff
+}
+
+fn write_snapshot(path: &FsPath, actual: &str) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::File::create(path)
+        .and_then(|mut file| file.write_all(actual.as_bytes()))
+        .unwrap_or_else(|err| panic!("can't write snapshot `{}`: {}", path.display(), err));
+}
+
+/// Compares `actual` against the snapshot file at `path`, panicking with their `text_diff` on
+/// a mismatch. The file is written instead if it doesn't exist or `VTREE_UPDATE_SNAPSHOTS` is
+/// set.
+pub fn assert_snapshot<P: AsRef<FsPath>>(path: P, actual: &str) {
+    let path = path.as_ref();
+    if env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() || !path.exists() {
+        write_snapshot(path, actual);
+        return;
+    }
+
+    let mut expected = String::new();
+    fs::File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut expected))
+        .unwrap_or_else(|err| panic!("can't read snapshot `{}`: {}", path.display(), err));
+    if expected != actual {
+        panic!("snapshot `{}` doesn't match, rerun with `{}=1` to update it:\n{}",
+               path.display(),
+               UPDATE_SNAPSHOTS_VAR,
+               text_diff(&expected, actual));
+    }
+}
+
+/// Small xorshift64* generator, deterministic for a seed so failures can be reproduced.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // splitmix64 step, so that small seeds give unrelated states and 0 isn't stuck
+        let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        Rng((z ^ (z >> 31)) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    /// Uniform in `0..n`, `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// `true` once in `n` times on average.
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Runs `property` with an `Rng` seeded with `0..cases`, or only with the seed in `VTREE_SEED`
+/// if it is set. Panics with the seed of the first failing case.
+pub fn check_property<F>(cases: u64, property: F)
+    where F: Fn(&mut Rng) -> Result<(), String>
+{
+    let seeds = match env::var(SEED_VAR) {
+        Ok(seed) => {
+            let seed = seed.parse()
+                .unwrap_or_else(|_| panic!("`{}` must be a number, got `{}`", SEED_VAR, seed));
+            seed..seed + 1
+        }
+        Err(_) => 0..cases,
+    };
+    for seed in seeds {
+        let mut rng = Rng::new(seed);
+        match panic::catch_unwind(AssertUnwindSafe(|| property(&mut rng))) {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => panic!("property failed, rerun with `{}={}`:\n{}", SEED_VAR, seed, err),
+            Err(_) => panic!("property panicked, rerun with `{}={}`", SEED_VAR, seed),
+        }
+    }
+}
+
+static STR_KEYS: [&'static str; 6] = ["k0", "k1", "k2", "k3", "k4", "k5"];
+
+/// A key out of a small pool, so that independently generated keys collide often. The pool has
+/// `Key::Str` and `Key::String` keys with the same text, which are equal.
+pub fn arbitrary_key(rng: &mut Rng) -> Key {
+    let n = rng.below(STR_KEYS.len());
+    match rng.below(5) {
+        0 => Key::U64(n as u64),
+        1 => Key::I64(n as i64 - 3),
+        2 => Key::Str(STR_KEYS[n]),
+        3 => Key::String(Rc::new(STR_KEYS[n].to_string())),
+        _ => Key::Auto(n as u64),
+    }
+}
+
+/// A key not in `used`, `None` if the pool ran out.
+fn fresh_key(rng: &mut Rng, used: &[&Key]) -> Option<Key> {
+    (0..20).map(|_| arbitrary_key(rng)).find(|key| !used.contains(&key))
+}
+
+/// Up to `max_len` distinct keys, e.g. for the children of a `Multi`.
+pub fn arbitrary_keys(rng: &mut Rng, max_len: usize) -> Vec<Key> {
+    let mut keys = Vec::new();
+    for _ in 0..rng.below(max_len + 1) {
+        let key = fresh_key(rng, &keys.iter().collect::<Vec<_>>());
+        keys.extend(key);
+    }
+    keys
+}
+
+/// Removes, inserts and moves some of `items`, `fresh` creates new ones given the kept ones.
+fn mutate_list<T, F>(rng: &mut Rng, items: &mut Vec<T>, mut fresh: F)
+    where F: FnMut(&mut Rng, &[T]) -> Option<T>
+{
+    items.retain(|_| !rng.one_in(4));
+    for _ in 0..rng.below(3) {
+        if let Some(item) = fresh(rng, items) {
+            let index = rng.below(items.len() + 1);
+            items.insert(index, item);
+        }
+    }
+    if rng.one_in(3) {
+        rng.shuffle(items);
+    } else if items.len() > 1 && rng.one_in(2) {
+        let (a, b) = (rng.below(items.len()), rng.below(items.len()));
+        items.swap(a, b);
+    }
+}
+
+/// `keys` with some of them removed, inserted and moved, still distinct.
+pub fn mutate_keys(rng: &mut Rng, keys: &[Key]) -> Vec<Key> {
+    let mut keys = keys.to_vec();
+    mutate_list(rng, &mut keys, |rng, keys| fresh_key(rng, &keys.iter().collect::<Vec<_>>()));
+    keys
+}
+
+const MAX_DEPTH: usize = 4;
+const MAX_CHILDREN: usize = 4;
+
+/// Generates template nodes following a schema, skipping the nodes with required params which
+/// can't be set from templates.
+struct TreeGen<'a> {
+    schema: &'a [NodeSchema],
+    rng: &'a mut Rng,
+}
+
+impl<'a> TreeGen<'a> {
+    fn node_schema(&self, name: &str) -> Option<&'a NodeSchema> {
+        self.schema.iter().find(|node| node.name == name)
+    }
+
+    fn text(&mut self, key: Option<Key>) -> template::Node {
+        template::Node::Text {
+            text: ["", "a", "b"][self.rng.below(3)].to_string(),
+            key: key,
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    /// A random node out of `names`, or text if `text` is set, `None` if none of them fits
+    /// within `depth`.
+    fn one_of(&mut self, names: &[&'static str], text: bool, key: Option<Key>, depth: usize)
+              -> Option<template::Node> {
+        let mut names = names.to_vec();
+        self.rng.shuffle(&mut names);
+        if text && self.rng.below(names.len() + 1) == 0 {
+            return Some(self.text(key));
+        }
+        for name in names {
+            if let Some(node) = self.node_schema(name).and_then(|node| self.node(node, key.clone(), depth)) {
+                return Some(node);
+            }
+        }
+        if text { Some(self.text(key)) } else { None }
+    }
+
+    fn node(&mut self, node: &NodeSchema, key: Option<Key>, depth: usize)
+            -> Option<template::Node> {
+        if node.params.iter().any(|param| param.required && param.values.is_empty()) {
+            return None;
+        }
+        let children = match node.children {
+            Some(ref schema) => self.children(schema, depth)?,
+            None => Vec::new(),
+        };
+        let mut slots = Vec::new();
+        for slot in &node.slots {
+            let children = self.children(&slot.children, depth)?;
+            if !children.is_empty() {
+                slots.push(template::Slot {
+                    name: slot.name.to_string(),
+                    children: children,
+                    position: Position { line: 1, column: 1 },
+                });
+            }
+        }
+        Some(template::Node::Node {
+            name: node.name.to_string(),
+            key: key,
+            params: self.params(node),
+            children: children,
+            slots: slots,
+            position: Position { line: 1, column: 1 },
+        })
+    }
+
+    /// Children of a slot following `schema`, `None` if a Single slot can't be filled within
+    /// `depth`.
+    fn children(&mut self, schema: &ChildrenSchema, depth: usize) -> Option<Vec<template::Node>> {
+        let len = match schema.kind {
+            ChildrenKind::Single if depth == 0 => return None,
+            ChildrenKind::Single => 1,
+            _ if depth == 0 => 0,
+            ChildrenKind::Optional => self.rng.below(2),
+            ChildrenKind::Multi => self.rng.below(MAX_CHILDREN + 1),
+        };
+        let mut children = Vec::new();
+        for _ in 0..len {
+            let key = self.child_key(schema.kind, &children);
+            children.extend(self.one_of(&schema.nodes, schema.text, key, depth - 1));
+        }
+        if schema.kind == ChildrenKind::Single && children.is_empty() {
+            return None;
+        }
+        Some(children)
+    }
+
+    /// The required and some other fields of `node` which can be set from templates, with
+    /// random values.
+    fn params(&mut self, node: &NodeSchema) -> Vec<template::Param> {
+        let mut params = Vec::new();
+        for param in &node.params {
+            if param.values.is_empty() || !param.required && self.rng.one_in(2) {
+                continue;
+            }
+            params.push(template::Param {
+                name: param.name.to_string(),
+                value: param.values[self.rng.below(param.values.len())].clone(),
+                position: Position { line: 1, column: 1 },
+            });
+        }
+        params
+    }
+
+    /// A key for a new child besides `siblings`, Multi children are left unkeyed at times.
+    fn child_key(&mut self, kind: ChildrenKind, siblings: &[template::Node]) -> Option<Key> {
+        match kind {
+            ChildrenKind::Multi if !self.rng.one_in(4) => {
+                let used: Vec<_> = siblings.iter().filter_map(|node| node.key()).collect();
+                fresh_key(self.rng, &used).and_then(|key| match key {
+                    Key::Auto(_) => None,
+                    key => Some(key),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// A root node, preferring the ones with children.
+    fn root(&mut self, depth: usize) -> template::Node {
+        let (mut parents, mut leaves): (Vec<_>, Vec<_>) = self.schema
+            .iter()
+            .partition(|node| node.children.is_some() || !node.slots.is_empty());
+        self.rng.shuffle(&mut parents);
+        self.rng.shuffle(&mut leaves);
+        parents.into_iter()
+            .chain(leaves)
+            .filter_map(|node| self.node(node, None, depth))
+            .next()
+            .expect("none of the nodes of the schema can be loaded from a template")
+    }
+
+    fn mutate(&mut self, node: &template::Node) -> template::Node {
+        let (name, key, params, children, slots) = match *node {
+            template::Node::Node { ref name, ref key, ref params, ref children, ref slots, .. } => {
+                (name, key, params, children, slots)
+            }
+            template::Node::Text { ref key, .. } => return self.text(key.clone()),
+        };
+        let node_schema = match self.node_schema(name) {
+            Some(node_schema) => node_schema,
+            None => return node.clone(),
+        };
+        let params = if self.rng.one_in(3) {
+            self.params(node_schema)
+        } else {
+            params.clone()
+        };
+        let children = match node_schema.children {
+            Some(ref schema) => self.mutate_children(schema, children),
+            None => children.clone(),
+        };
+        let mut mutated_slots = Vec::new();
+        for schema in &node_schema.slots {
+            let slot = slots.iter().find(|slot| slot.name == schema.name);
+            let children = slot.map_or(&[][..], |slot| &slot.children[..]);
+            let children = self.mutate_children(&schema.children, children);
+            if !children.is_empty() {
+                mutated_slots.push(template::Slot {
+                    name: schema.name.to_string(),
+                    children: children,
+                    position: slot.map_or(node.position(), |slot| slot.position),
+                });
+            }
+        }
+        template::Node::Node {
+            name: name.clone(),
+            key: key.clone(),
+            params: params,
+            children: children,
+            slots: mutated_slots,
+            position: node.position(),
+        }
+    }
+
+    fn mutate_children(&mut self, schema: &ChildrenSchema, children: &[template::Node])
+                       -> Vec<template::Node> {
+        let mut children: Vec<_> = children.iter()
+            .map(|child| {
+                if self.rng.one_in(6) {
+                    self.one_of(&schema.nodes, schema.text, child.key().cloned(), 2)
+                        .unwrap_or_else(|| child.clone())
+                } else if self.rng.one_in(2) {
+                    self.mutate(child)
+                } else {
+                    child.clone()
+                }
+            })
+            .collect();
+        match schema.kind {
+            ChildrenKind::Single => {}
+            ChildrenKind::Optional => {
+                if children.is_empty() && self.rng.one_in(3) {
+                    children.extend(self.one_of(&schema.nodes, schema.text, None, 2));
+                } else if self.rng.one_in(3) {
+                    children.clear();
+                }
+            }
+            ChildrenKind::Multi => {
+                let all = self.schema;
+                mutate_list(self.rng, &mut children, |rng, children| {
+                    let mut gen = TreeGen {
+                        schema: all,
+                        rng: rng,
+                    };
+                    let key = gen.child_key(ChildrenKind::Multi, children);
+                    gen.one_of(&schema.nodes, schema.text, key, 2)
+                });
+            }
+        }
+        children
+    }
+}
+
+/// A random template of a tree following the schema of `AN`, e.g. `AllNodes`. Params are set
+/// to sample values of their fields, text is random.
+pub fn arbitrary_template<AN: TemplateSchema>(rng: &mut Rng) -> template::Node {
+    let schema = AN::template_schema();
+    TreeGen {
+        schema: &schema,
+        rng: rng,
+    }.root(MAX_DEPTH)
+}
+
+/// `node` with children removed, inserted, moved, replaced and mutated recursively, still
+/// following the schema of `AN`.
+pub fn mutate_template<AN: TemplateSchema>(rng: &mut Rng, node: &template::Node)
+                                           -> template::Node {
+    let schema = AN::template_schema();
+    let mut gen = TreeGen {
+        schema: &schema,
+        rng: rng,
+    };
+    if gen.rng.one_in(10) {
+        gen.root(MAX_DEPTH)
+    } else {
+        gen.mutate(node)
+    }
+}
+
+fn load<AN: FromTemplate>(node: &template::Node) -> AN {
+    AN::from_template(node).unwrap_or_else(|err| {
+        panic!("can't load a generated template: {}\n{:?}", err, node)
+    })
+}
+
+/// A random tree following the schema of `AN`.
+pub fn arbitrary_tree<AN: TemplateSchema + FromTemplate>(rng: &mut Rng) -> AN {
+    load(&arbitrary_template::<AN>(rng))
+}
+
+/// A random last tree and a curr tree mutated from it, as `(curr, last)`.
+pub fn arbitrary_tree_pair<AN: TemplateSchema + FromTemplate>(rng: &mut Rng) -> (AN, AN) {
+    let last = arbitrary_template::<AN>(rng);
+    let curr = mutate_template::<AN>(rng, &last);
+    (load(&curr), load(&last))
+}
+
+fn duplicate<T: PartialEq>(items: &[T]) -> Option<&T> {
+    items.iter().enumerate().find(|&(i, a)| items[..i].contains(a)).map(|(_, a)| a)
+}
+
+/// Checks that no key is used twice among the children of a slot anywhere in `tree`.
+pub fn check_unique_keys<AN: NodeChildren>(tree: &AN) -> Result<(), String> {
+    fn check<AN: NodeChildren>(node: &AN, path: &mut Path) -> Result<(), String> {
+        let mut children = Vec::new();
+        node.for_each_child(|entry, child| children.push((entry.to_path_entry(), child)));
+        let entries: Vec<_> = children.iter().map(|&(ref entry, _)| entry).collect();
+        if let Some(entry) = duplicate(&entries) {
+            return Err(format!("`{}` is used twice in {}", entry, path_str(path)));
+        }
+        for (entry, child) in children {
+            let mut path = path.clone();
+            path.push(entry);
+            check(child, &mut path)?;
+        }
+        Ok(())
+    }
+
+    let mut path = Path::new();
+    path.push(PathEntry::Empty);
+    check(tree, &mut path)
+}
+
+/// Checks that the `(curr_index, index)` pairs passed to `Differ::diff_reordered` for a
+/// `Multi` of `len` children are a permutation: every index in range and moved at most once,
+/// and the indices moved to are the ones moved from.
+pub fn check_permutation(indices: &[(usize, usize)], len: usize) -> Result<(), String> {
+    if let Some(&(c, l)) = indices.iter().find(|&&(c, l)| c >= len || l >= len) {
+        return Err(format!("reorder ({}, {}) is out of range for {} children", c, l, len));
+    }
+    let mut curr: Vec<_> = indices.iter().map(|&(c, _)| c).collect();
+    let mut last: Vec<_> = indices.iter().map(|&(_, l)| l).collect();
+    if let Some(c) = duplicate(&curr) {
+        return Err(format!("reorders {:?} move to {} twice", indices, c));
+    }
+    if let Some(l) = duplicate(&last) {
+        return Err(format!("reorders {:?} move {} twice", indices, l));
+    }
+    curr.sort();
+    last.sort();
+    if curr != last {
+        return Err(format!("reorders {:?} aren't a permutation", indices));
+    }
+    Ok(())
+}
+
+/// Moves `items[l]` to `c` for every `(c, l)` of `indices`.
+fn apply_reorder<T: Clone>(items: &mut [T], indices: &[(usize, usize)]) -> Result<(), String> {
+    check_permutation(indices, items.len())?;
+    let last = items.to_vec();
+    for &(c, l) in indices {
+        items[c] = last[l].clone();
+    }
+    Ok(())
+}
+
+/// Checks that replaying `curr.diff(last)` and `curr.diff_reordered(last)` on the keys of last
+/// gives the keys of curr: removing the removed keys, inserting the added ones at their curr
+/// index in order and applying the reorders.
+pub fn check_multi_diff<G: Into<AN>, AN>(curr: &Multi<G, AN>, last: &Multi<G, AN>)
+                                         -> Result<(), String> {
+    let curr_keys: Vec<_> = curr.iter().map(|(key, _)| key.clone()).collect();
+    let last_keys: Vec<_> = last.iter().map(|(key, _)| key.clone()).collect();
+    for keys in &[&curr_keys, &last_keys] {
+        if let Some(key) = duplicate(keys) {
+            return Err(format!("`{}` is used twice in {:?}", key, keys));
+        }
+    }
+
+    let mut keys = last_keys.clone();
+    for (key, curr_child, last_child) in curr.diff(last) {
+        match (curr_child, last_child) {
+            (Some((index, _)), None) => {
+                let index = index.min(keys.len());
+                keys.insert(index, key.clone());
+            }
+            (None, Some(_)) => {
+                match keys.iter().position(|k| k == key) {
+                    Some(index) => keys.remove(index),
+                    None => return Err(format!("removed `{}` which isn't there", key)),
+                };
+            }
+            _ => {}
+        }
+    }
+    let indices: Vec<_> = curr.diff_reordered(last).collect();
+    apply_reorder(&mut keys, &indices)?;
+    if keys != curr_keys {
+        return Err(format!("replaying the diff of {:?} against {:?} gives {:?}",
+                           curr_keys,
+                           last_keys,
+                           keys));
+    }
+    Ok(())
+}
+
+fn entry_slot(entry: &PathEntry) -> Option<&'static str> {
+    match *entry {
+        PathEntry::SlotKey(slot, _) | PathEntry::Slot(slot) => Some(slot),
+        PathEntry::Key(_) | PathEntry::Empty => None,
+    }
+}
+
+/// Node heads and children of a tree, which the `Replay` differ edits. Empty slots are
+/// ignored.
+#[derive(Debug, Clone)]
+struct Model {
+    head: String,
+    slots: BTreeMap<Option<&'static str>, Vec<(PathEntry, Model)>>,
+}
+
+impl Model {
+    fn of<AN: MarkupNode>(node: &AN) -> Model {
+        let mut slots = BTreeMap::new();
+        node.for_each_child(|entry, child| {
+            slots.entry(entry.slot())
+                .or_insert_with(Vec::new)
+                .push((entry.to_path_entry(), Model::of(child)));
+        });
+        Model {
+            head: Model::head(node),
+            slots: slots,
+        }
+    }
+
+    fn head<AN: MarkupNode>(node: &AN) -> String {
+        match node.markup_head() {
+            MarkupHead::Node { name, params } => {
+                params.into_iter().fold(name.to_string(), |head, (name, value)| {
+                    format!("{} {}={}", head, name, value)
+                })
+            }
+            MarkupHead::Text(text) => format!("{:?}", text),
+            MarkupHead::Widget => "// widget".to_string(),
+        }
+    }
+
+    fn find_mut<'a, 'b, I>(&'a mut self, entries: I) -> Option<&'a mut Model>
+        where I: Iterator<Item = &'b PathEntry>
+    {
+        let mut model = self;
+        for entry in entries {
+            model = model.slots
+                .get_mut(&entry_slot(entry))
+                .and_then(|children| children.iter_mut().find(|child| child.0 == *entry))
+                .map(|child| &mut child.1)?;
+        }
+        Some(model)
+    }
+
+    fn write(&self, entry: &PathEntry, depth: usize, f: &mut fmt::Formatter) -> fmt::Result {
+        let indent = "    ".repeat(depth);
+        writeln!(f, "{}{} {}", indent, entry, self.head)?;
+        for (slot, children) in self.slots.iter().filter(|&(_, children)| !children.is_empty()) {
+            writeln!(f, "{}    // {}", indent, slot.unwrap_or("children"))?;
+            for &(ref entry, ref child) in children {
+                child.write(entry, depth + 1, f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(&PathEntry::Empty, 0, f)
+    }
+}
+
+/// `Differ` applying the callbacks to a `Model` of last, collecting the ones which can't be
+/// applied.
+#[derive(Debug)]
+struct Replay {
+    model: Model,
+    errors: Vec<String>,
+}
+
+impl Replay {
+    fn node<AN>(&mut self, frame: &PathFrame<AN>) -> Option<&mut Model> {
+        let path = frame.to_path();
+        let node = self.model.find_mut(path.iter().skip(1));
+        if node.is_none() {
+            self.errors.push(format!("no node at {}", path_str(&path)));
+        }
+        node
+    }
+
+    /// The children of the slot of `frame` in its parent.
+    fn siblings<AN>(&mut self, frame: &PathFrame<AN>) -> Option<&mut Vec<(PathEntry, Model)>> {
+        let path = frame.to_path();
+        if path.len() <= 1 {
+            self.errors.push("the root was added or removed".to_string());
+            return None;
+        }
+        match self.model.find_mut(path.iter().skip(1).take(path.len() - 2)) {
+            Some(parent) => {
+                Some(parent.slots.entry(frame.path_index_entry().slot()).or_insert_with(Vec::new))
+            }
+            None => {
+                self.errors.push(format!("no parent of {}", path_str(&path)));
+                None
+            }
+        }
+    }
+}
+
+impl<CTX, AN: MarkupNode> Differ<CTX, AN> for Replay {
+    fn diff_added(&mut self, _ctx: &mut Context<CTX, AN>, curr: &PathFrame<AN>) {
+        let entry = curr.to_path_entry();
+        let mut error = None;
+        if let Some(children) = self.siblings(curr) {
+            if children.iter().any(|child| child.0 == entry) {
+                error = Some(format!("added {} which is there", path_str(&curr.to_path())));
+            } else {
+                let index = curr.path_index_entry().index().unwrap_or(0).min(children.len());
+                children.insert(index, (entry, Model::of(curr.node())));
+            }
+        }
+        self.errors.extend(error);
+    }
+
+    fn diff_removed(&mut self, _ctx: &mut Context<CTX, AN>, last: &PathFrame<AN>) {
+        let entry = last.to_path_entry();
+        let mut error = None;
+        if let Some(children) = self.siblings(last) {
+            match children.iter().position(|child| child.0 == entry) {
+                Some(index) => {
+                    children.remove(index);
+                }
+                None => {
+                    error = Some(format!("removed {} which isn't there",
+                                         path_str(&last.to_path())));
+                }
+            }
+        }
+        self.errors.extend(error);
+    }
+
+    /// Replaces the node in place, keeping its position for the reorders.
+    fn diff_replaced(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+        if let Some(node) = self.node(curr) {
+            *node = Model::of(curr.node());
+        }
+    }
+
+    fn diff_params_changed(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        _last: &PathFrame<AN>,
+    ) {
+        if let Some(node) = self.node(curr) {
+            node.head = Model::head(curr.node());
+        }
+    }
+
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        parent: &PathFrame<AN>,
+        slot: Option<&'static str>,
+        indices: I,
+    ) {
+        let indices: Vec<_> = indices.collect();
+        let path = path_str(&parent.to_path());
+        let result = match self.node(parent) {
+            Some(node) => apply_reorder(node.slots.entry(slot).or_insert_with(Vec::new), &indices),
+            None => return,
+        };
+        if let Err(err) = result {
+            self.errors.push(format!("{} of {} {}", err, path, slot.unwrap_or("children")));
+        }
+    }
+}
+
+/// Checks that replaying the `Differ` callbacks of diffing `curr` against `last`, both with
+/// their widgets expanded, on last gives curr. Removed children are removed, added ones
+/// inserted at their curr index in the order they're reported, replaced ones and changed params
+/// updated in place and reorders applied after checking that they're permutations. Both trees
+/// are checked with `check_unique_keys` as well.
+pub fn check_diff<AN: TemplateRoot + MarkupNode + Clone>(curr: &AN, last: &AN)
+                                                         -> Result<(), String> {
+    let (curr, last) = (expanded(curr), expanded(last));
+    check_unique_keys(&curr)?;
+    check_unique_keys(&last)?;
+
+    let mut replay = Replay {
+        model: Model::of(&last),
+        errors: Vec::new(),
+    };
+    AN::diff(&PathFrame::new(&curr),
+             &PathFrame::new(&last),
+             &mut Context::new(()),
+             &mut replay);
+    if !replay.errors.is_empty() {
+        return Err(replay.errors.join("\n"));
+    }
+    let (expected, actual) = (Model::of(&curr).to_string(), replay.model.to_string());
+    if expected != actual {
+        return Err(format!("replaying the diff on last doesn't give curr:\n{}",
+                           text_diff(&expected, &actual)));
+    }
+    Ok(())
+}
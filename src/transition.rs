@@ -0,0 +1,261 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use diff::{Context, Differ, Path, PathFrame};
+use node::NodeChildren;
+
+/// Source of the current time for `Transitions`, relative to an arbitrary but fixed point.
+pub trait Clock: Debug {
+    fn now(&self) -> Duration;
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock which only moves when told to, e.g. in tests.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: Cell<Duration>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+impl<'a, C: Clock> Clock for &'a C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+impl<C: Clock> Clock for Rc<C> {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// Numeric fields of a params type which are interpolated on change. `define_params!`
+/// implements it for every params struct, using the fields marked with `#[animate]` in
+/// declaration order.
+pub trait AnimatedParams {
+    fn animated_values(&self) -> Vec<f64>;
+    fn set_animated_values(&mut self, values: &[f64]);
+}
+
+/// Implemented for `AllNodes` by `define_nodes!` by forwarding to the node's params.
+pub trait AnimatedNode: NodeChildren + Clone {
+    fn animated_values(&self) -> Vec<f64>;
+    fn set_animated_values(&mut self, values: &[f64]);
+}
+
+#[derive(Debug, Clone)]
+struct Tween {
+    from: Vec<f64>,
+    to: Vec<f64>,
+    start: Duration,
+}
+
+/// A removed node kept alive for its exit animation.
+#[derive(Debug, Clone)]
+pub struct Exiting<AN> {
+    pub path: Path,
+    pub node: AN,
+    start: Duration,
+}
+
+/// A node moved from index `from` to `to` within its parent by a reorder, the indices being the
+/// ones passed to `Differ::diff_reordered`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    start: Duration,
+}
+
+/// Transition state driven by `Differ` callbacks and keyed by `Path`.
+///
+/// Removed nodes are kept as `Exiting` until their exit animation finished, params changes
+/// interpolate the animated values from the last to the current ones and reorders are recorded
+/// as `Move`s. All transitions take `duration`, progress is read from the clock `C`.
+#[derive(Debug)]
+pub struct Transitions<C: Clock, AN> {
+    clock: C,
+    duration: Duration,
+    exiting: Vec<Exiting<AN>>,
+    tweens: HashMap<Path, Tween>,
+    moves: HashMap<Path, Move>,
+}
+
+impl<C: Clock, AN: AnimatedNode> Transitions<C, AN> {
+    pub fn new(clock: C, duration: Duration) -> Transitions<C, AN> {
+        Transitions {
+            clock: clock,
+            duration: duration,
+            exiting: Vec::new(),
+            tweens: HashMap::new(),
+            moves: HashMap::new(),
+        }
+    }
+
+    /// Progress of a transition started at `start`, from `0.0` to `1.0`.
+    fn progress(&self, start: Duration) -> f64 {
+        let elapsed = self.clock.now().checked_sub(start).unwrap_or_default();
+        if self.duration == Duration::default() || self.duration <= elapsed {
+            return 1.0;
+        }
+        elapsed.as_secs_f64() / self.duration.as_secs_f64()
+    }
+
+    /// Drops finished transitions, returns whether any transition is still running.
+    pub fn tick(&mut self) -> bool {
+        let now = self.clock.now();
+        let duration = self.duration;
+        let running = |start: Duration| now < start + duration;
+        self.exiting.retain(|e| running(e.start));
+        self.tweens.retain(|_, t| running(t.start));
+        self.moves.retain(|_, m| running(m.start));
+        self.is_running()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.exiting.is_empty() || !self.tweens.is_empty() || !self.moves.is_empty()
+    }
+
+    /// Removed nodes still animating their exit, with their progress.
+    pub fn exiting<'a>(&'a self) -> impl Iterator<Item = (&'a Exiting<AN>, f64)> + 'a {
+        self.exiting.iter().map(move |e| (e, self.progress(e.start)))
+    }
+
+    /// Running move of the node at `path`, with its progress.
+    pub fn moved(&self, path: &Path) -> Option<(Move, f64)> {
+        self.moves.get(path).map(|m| (*m, self.progress(m.start)))
+    }
+
+    /// Current interpolated animated values of the node at `path`, if they are transitioning.
+    pub fn values(&self, path: &Path) -> Option<Vec<f64>> {
+        self.tweens.get(path).map(|tween| {
+            let p = self.progress(tween.start);
+            tween.from
+                .iter()
+                .zip(tween.to.iter())
+                .map(|(from, to)| from + (to - from) * p)
+                .collect()
+        })
+    }
+
+    /// Returns a clone of `node` with its animated values set to the current interpolated
+    /// ones, or `None` if the node at `path` isn't transitioning.
+    pub fn interpolated(&self, path: &Path, node: &AN) -> Option<AN> {
+        self.values(path).map(|values| {
+            let mut node = node.clone();
+            node.set_animated_values(&values);
+            node
+        })
+    }
+}
+
+impl<CTX, C: Clock, AN: AnimatedNode + Debug> Differ<CTX, AN> for Transitions<C, AN> {
+    fn diff_added(&mut self, _ctx: &mut Context<CTX, AN>, curr: &PathFrame<AN>) {
+        let path = curr.to_path();
+        self.exiting.retain(|e| e.path != path);
+    }
+
+    fn diff_removed(&mut self, _ctx: &mut Context<CTX, AN>, last: &PathFrame<AN>) {
+        let path = last.to_path();
+        self.tweens.retain(|p, _| !p.starts_with(&path));
+        self.moves.retain(|p, _| !p.starts_with(&path));
+        self.exiting.push(Exiting {
+            path: path,
+            node: last.node().clone(),
+            start: self.clock.now(),
+        });
+    }
+
+    /// The replaced node exits while its replacement is already shown at the same path.
+    fn diff_replaced(
+        &mut self,
+        ctx: &mut Context<CTX, AN>,
+        _curr: &PathFrame<AN>,
+        last: &PathFrame<AN>,
+    ) {
+        self.diff_removed(ctx, last);
+    }
+
+    fn diff_params_changed(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        curr: &PathFrame<AN>,
+        last: &PathFrame<AN>,
+    ) {
+        let path = curr.to_path();
+        let to = curr.node().animated_values();
+        // continue from the current values of a running transition
+        let from = self.values(&path).unwrap_or_else(|| last.node().animated_values());
+        if from == to || from.len() != to.len() {
+            return;
+        }
+        let tween = Tween {
+            from: from,
+            to: to,
+            start: self.clock.now(),
+        };
+        self.tweens.insert(path, tween);
+    }
+
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(
+        &mut self,
+        _ctx: &mut Context<CTX, AN>,
+        parent: &PathFrame<AN>,
+        slot: Option<&'static str>,
+        indices: I,
+    ) {
+        let indices: HashMap<usize, usize> = indices.collect();
+        if indices.is_empty() {
+            return;
+        }
+        let parent_path = parent.to_path();
+        let start = self.clock.now();
+        let moves = &mut self.moves;
+        parent.node().for_each_child(|entry, _| {
+            let index = match entry.index() {
+                Some(index) if entry.slot() == slot => index,
+                _ => return,
+            };
+            if let Some(&from) = indices.get(&index) {
+                moves.insert(parent_path.join(entry.to_path_entry()), Move {
+                    from: from,
+                    to: index,
+                    start: start,
+                });
+            }
+        });
+    }
+}
@@ -31,6 +31,24 @@ impl<O, W> WidgetDataTrait<O> for WidgetData<W>
     }
 }
 
+/// Widget data of a group `G` rendering into a group containing it, e.g. `AllNodes`.
+#[derive(Debug, Clone)]
+pub struct IntoWidgetData<G>(pub Box<WidgetDataTrait<G>>)
+    where G: Debug + Clone;
+
+impl<O, G> WidgetDataTrait<O> for IntoWidgetData<G>
+    where O: Debug + Clone,
+          G: Debug + Clone + Into<O> + 'static
+{
+    fn render(self: Box<Self>) -> Option<O> {
+        self.0.render().map(Into::into)
+    }
+
+    fn clone_box(&self) -> Box<WidgetDataTrait<O>> {
+        Box::new((*self).clone())
+    }
+}
+
 pub trait Widget: Debug + Clone {
     type Input: Debug + Clone;
     type Output: Debug + Clone;
@@ -0,0 +1,103 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use common::{path, Button, Dialog, Group, Label};
+use common::groups::AllNodes;
+use vtree::a11y::{self, A11yNode, A11yProps, A11yUpdate, A11yUpdates};
+use vtree::diff::{Context, PathFrame};
+use vtree_markup::markup;
+
+fn a11y_node(keys: &[&'static str], role: &'static str, label: &'static str,
+             children: Vec<A11yNode>) -> A11yNode {
+    A11yNode {
+        id: path(keys),
+        role: role,
+        props: A11yProps {
+            name: Some(label.to_string()),
+            ..A11yProps::default()
+        },
+        children: children,
+    }
+}
+
+fn diff(curr: &AllNodes, last: &AllNodes) -> Vec<A11yUpdate> {
+    let mut updates = A11yUpdates::new();
+    let mut ctx = Context::new(());
+    AllNodes::diff(&PathFrame::new(curr), &PathFrame::new(last), &mut ctx, &mut updates);
+    updates.take()
+}
+
+#[test]
+fn a11y_tree_skips_nodes_without_role() {
+    let root: AllNodes = markup!(Dialog label="root" {
+        Group@"a" {
+            Button@"b" label="ok" /
+            Button@"c" label="cancel" /
+        }
+        Label@"d" label="hint" /
+    });
+
+    assert_eq!(a11y::tree(&root), vec![
+        a11y_node(&[], "dialog", "root", vec![
+            a11y_node(&["a", "b"], "button", "ok", vec![]),
+            a11y_node(&["a", "c"], "button", "cancel", vec![]),
+            a11y_node(&["d"], "text", "hint", vec![]),
+        ]),
+    ]);
+}
+
+#[test]
+fn a11y_updates() {
+    let last: AllNodes = markup!(Dialog label="root" {
+        Group@"a" { Button@"b" label="ok" / }
+    });
+    let curr: AllNodes = markup!(Dialog label="root" {
+        Group@"a" { Button@"b" label="yes" / }
+    });
+
+    assert_eq!(diff(&curr, &last), vec![
+        A11yUpdate::Changed {
+            id: path(&["a", "b"]),
+            role: "button",
+            props: A11yProps {
+                name: Some("yes".to_string()),
+                ..A11yProps::default()
+            },
+        },
+    ]);
+
+    let last = curr;
+    let curr: AllNodes = markup!(Dialog label="root" {
+        Label@"a" label="yes" /
+    });
+    assert_eq!(diff(&curr, &last), vec![
+        A11yUpdate::Removed { ids: vec![path(&["a", "b"])] },
+        A11yUpdate::Added {
+            parent: Some(path(&[])),
+            nodes: vec![a11y_node(&["a"], "text", "yes", vec![])],
+        },
+    ]);
+    assert_eq!(diff(&curr, &curr), vec![]);
+}
+
+#[test]
+fn a11y_reordered() {
+    let last: AllNodes = markup!(Group {
+        Button@"a" label="a" /
+        Group@"b" { Button@"c" label="c" / }
+    });
+    let curr: AllNodes = markup!(Group {
+        Group@"b" { Button@"c" label="c" / }
+        Button@"a" label="a" /
+    });
+
+    assert_eq!(diff(&curr, &last), vec![
+        A11yUpdate::Reordered {
+            parent: None,
+            children: vec![path(&["b", "c"]), path(&["a"])],
+        },
+    ]);
+}
@@ -0,0 +1,56 @@
+extern crate vtree;
+extern crate vtree_macros;
+
+use std::collections::HashSet;
+use vtree_macros::define_nodes;
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+/// Stands in for the `cfg`'d out node, clashing with it if it was generated.
+pub struct Hidden;
+
+define_nodes!{
+    nodes {
+        /// A node documented with a line comment.
+        #[derive(PartialEq, Eq, Hash, Default)]
+        Leaf,
+        /** A node documented with a block comment. */
+        #[derive(PartialEq, Default)]
+        Row: mul @Inline,
+        #[doc = "A node documented with an attribute."]
+        #[cfg(any())]
+        #[derive(PartialEq)]
+        Hidden,
+        #[cfg(all())]
+        #[derive(PartialEq)]
+        Shown,
+    }
+    groups {
+        /// Nodes within a row.
+        #[derive(PartialEq)]
+        Inline: Leaf Row Hidden Shown,
+    }
+}
+
+#[test]
+fn derives_are_forwarded() {
+    assert_eq!(Leaf::default(), Leaf::new());
+    let leaves: HashSet<Leaf> = vec![Leaf::new(), Leaf::new()].into_iter().collect();
+    assert_eq!(leaves.len(), 1);
+    assert_eq!(Row::default(), Row::new(Default::default()));
+}
+
+#[test]
+fn group_partial_eq() {
+    let leaf: groups::Inline = Leaf::new().into();
+    let shown: groups::Inline = Shown::new().into();
+    assert_eq!(leaf, Leaf::new().into());
+    assert!(leaf != shown);
+}
+
+#[test]
+fn cfg_is_forwarded() {
+    let _: Hidden = Hidden;
+    let _: groups::Inline = Shown::new().into();
+}
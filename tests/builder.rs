@@ -0,0 +1,157 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use common::{BoxParams, Group, Image};
+use common::groups::AllNodes;
+use links::{Link, LinkParams};
+use vtree::node::{self, ParamsError};
+use vtree::template::{self, TemplateSchema};
+use vtree_markup::markup;
+
+/// Node with params checked by `validate` and `finalize` hooks.
+mod links {
+    use vtree_macros::{define_nodes, define_params};
+
+    #[derive(Debug)]
+    pub enum AllEvent {}
+
+    fn trimmed(href: String) -> Result<String, String> {
+        match href.trim() {
+            "" => Err("empty href".into()),
+            href => Ok(href.into()),
+        }
+    }
+
+    fn labelled(mut params: LinkParams) -> Result<LinkParams, String> {
+        if params.label.is_empty() {
+            params.label = params.href.clone();
+        }
+        Ok(params)
+    }
+
+    define_params!{
+        #[derive(Debug, Clone, Default, PartialEq)]
+        #[finalize = "labelled"]
+        pub struct LinkParams {
+            #[validate = "trimmed"]
+            pub href: String,
+            pub label: String,
+        }
+    }
+
+    define_nodes!{
+        nodes {
+            Link<::links::LinkParams>,
+        }
+        groups {}
+    }
+}
+
+fn link(href: &str, label: &str) -> LinkParams {
+    Link::builder()
+        .params()
+        .set_href(href.into())
+        .set_label(label.into())
+        .build()
+        .build()
+        .params
+}
+
+#[test]
+fn build_with_default_params() {
+    let group = Group::builder().build();
+    assert_eq!(group.params, BoxParams::default());
+    match markup!(Group /) {
+        AllNodes::Group(ref group) => assert_eq!(group.params, BoxParams::default()),
+        ref node => panic!("expected a group, got {:?}", node),
+    }
+}
+
+#[test]
+fn default_nodes_with_params() {
+    let group = Group::default();
+    assert_eq!(group.params, BoxParams::default());
+    assert_eq!(group.children.iter().count(), 0);
+}
+
+#[test]
+fn build_with_set_params() {
+    let image = Image::builder().params().set_src("a.png".into()).set_alt("a".into()).build().build();
+    let params = image.params;
+    assert_eq!((&params.src[..], &params.alt[..]), ("a.png", "a"));
+    assert_eq!(Image::builder().set_params(params.clone()).build().params, params);
+    match markup!(Image src="a.png" alt="a" /) {
+        AllNodes::Image(ref image) => assert_eq!(image.params, params),
+        ref node => panic!("expected an image, got {:?}", node),
+    }
+}
+
+#[test]
+fn required_params_have_no_default() {
+    // `ImageParams` derives `Default`, which can't know its required `src`
+    assert!(node::default_params::<common::ImageParams>().is_none());
+    let schema = AllNodes::template_schema();
+    let image = schema.iter().find(|node| node.name == "Image").unwrap();
+    let required: Vec<_> = image.params.iter().map(|param| (param.name, param.required)).collect();
+    assert_eq!(required, vec![("src", true), ("alt", false)]);
+}
+
+#[test]
+fn hooks_run_on_params_builder() {
+    let params = link(" /a ", "");
+    assert_eq!((&params.href[..], &params.label[..]), ("/a", "/a"));
+    let err = Link::builder().params().set_href(" ".into()).try_build().err().unwrap();
+    assert_eq!(err, ParamsError {
+        params: "LinkParams",
+        field: Some("href"),
+        message: "empty href".into(),
+    });
+}
+
+#[test]
+fn hooks_run_on_set_params() {
+    let mut params = link("/a", "a");
+    params.href = " /b ".into();
+    params.label = "".into();
+    let params = Link::builder().set_params(params).build().params;
+    assert_eq!((&params.href[..], &params.label[..]), ("/b", "/b"));
+    let mut params = link("/a", "a");
+    params.href = "".into();
+    assert_eq!(Link::builder().try_set_params(params.clone()).err().unwrap().to_string(),
+               "invalid `LinkParams::href`: empty href");
+    let link: Link = markup!(Link=({
+        let mut params = params.clone();
+        params.href = " /c ".into();
+        params
+    }) /);
+    assert_eq!(link.params.href, "/c");
+}
+
+#[test]
+#[should_panic(expected = "invalid `LinkParams::href`: empty href")]
+fn hooks_reject_set_params() {
+    let mut params = link("/a", "a");
+    params.href = "".into();
+    Link::builder().set_params(params);
+}
+
+#[test]
+#[should_panic(expected = "default params rejected: invalid `LinkParams::href`: empty href")]
+fn hooks_run_on_default_params() {
+    Link::builder().build();
+}
+
+#[test]
+fn hooks_run_on_templates() {
+    use links::groups::AllNodes;
+    match template::from_str::<AllNodes>(r#"Link href=" /a " /"#).unwrap() {
+        AllNodes::Link(ref link) => assert_eq!(link.params, self::link("/a", "/a")),
+        ref node => panic!("expected a link, got {:?}", node),
+    }
+    let err = template::from_str::<AllNodes>("\n  Link label=\"a\" /").unwrap_err();
+    assert_eq!(err.message, "invalid `LinkParams::href`: empty href");
+    assert_eq!((err.position.line, err.position.column), (2, 3));
+}
@@ -0,0 +1,74 @@
+extern crate vtree;
+
+use vtree::child::Multi;
+use vtree::key::Key;
+
+fn multi(keys: &[u64]) -> Multi<(), ()> {
+    let mut multi = Multi::new();
+    for &key in keys {
+        multi.push(Key::from(key), ());
+    }
+    multi
+}
+
+fn reordered(curr: &[u64], last: &[u64]) -> Vec<(usize, usize)> {
+    multi(curr).diff_reordered(&multi(last)).collect()
+}
+
+/// Applies the diff of `curr` against `last` to the keys of last: removing, inserting the
+/// added keys at their curr index in order and moving by the reorders.
+fn replay(curr: &[u64], last: &[u64]) -> Vec<Key> {
+    let (curr, last) = (multi(curr), multi(last));
+    let mut keys: Vec<_> = last.iter().map(|(key, _)| key.clone()).collect();
+    for (key, curr_child, last_child) in curr.diff(&last) {
+        match (curr_child, last_child) {
+            (Some((index, _)), None) => keys.insert(index.min(keys.len()), key.clone()),
+            (None, Some(_)) => keys.retain(|k| k != key),
+            _ => {}
+        }
+    }
+    let moved = keys.clone();
+    let mut seen = vec![false; keys.len()];
+    for (c, l) in curr.diff_reordered(&last) {
+        assert!(!seen[c], "{} is moved to twice", c);
+        seen[c] = true;
+        keys[c] = moved[l].clone();
+    }
+    keys
+}
+
+#[test]
+fn multi_diff_reordered() {
+    // added children are inserted at their curr index, nothing moves
+    assert_eq!(reordered(&[0, 10, 11, 1, 2], &[0, 1, 2]), vec![]);
+    assert_eq!(reordered(&[10, 11, 12], &[]), vec![]);
+    assert_eq!(reordered(&[0, 1], &[0, 5, 1, 6]), vec![]);
+    assert_eq!(reordered(&[2, 0, 1], &[0, 1, 2]), vec![(1, 0), (2, 1), (0, 2)]);
+    assert_eq!(reordered(&[2, 10, 1], &[1, 0, 2]), vec![(2, 0), (0, 2)]);
+}
+
+#[test]
+fn multi_diff_replays() {
+    // every ordered selection of up to 4 out of 5 keys against each other
+    fn selections(len: usize) -> Vec<Vec<u64>> {
+        if len == 0 {
+            return vec![vec![]];
+        }
+        let mut all = selections(len - 1);
+        for keys in selections(len - 1).into_iter().filter(|keys| keys.len() == len - 1) {
+            for key in (0..5).filter(|key| !keys.contains(key)) {
+                let mut keys = keys.clone();
+                keys.push(key);
+                all.push(keys);
+            }
+        }
+        all
+    }
+    let all = selections(4);
+    for last in all.iter().filter(|keys| keys.len() <= 3) {
+        for curr in &all {
+            let expected: Vec<_> = curr.iter().map(|&key| Key::from(key)).collect();
+            assert_eq!(replay(curr, last), expected, "{:?} against {:?}", curr, last);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Node set shared by the integration tests, so they run against the impls generated by
+//! `define_params!` and `define_nodes!`.
+#![allow(dead_code)]
+
+use vtree::diff::{Path, PathEntry};
+use vtree::key::Key;
+use vtree::layout::Style;
+use vtree_macros::{define_nodes, define_params};
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+define_params!{
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct BoxParams {
+        #[layout]
+        pub style: Style,
+        #[focusable]
+        pub focusable: bool,
+        #[a11y = "label"]
+        pub label: String,
+        #[animate]
+        pub x: f64,
+    }
+}
+
+define_params!{
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct ImageParams {
+        #[required]
+        pub src: String,
+        #[a11y = "label"]
+        pub alt: String,
+    }
+}
+
+define_nodes!{
+    nodes {
+        Group<::common::BoxParams>: mul @Any,
+        #[role = "dialog"]
+        Dialog<::common::BoxParams>: mul @Any,
+        #[role = "button"]
+        Button<::common::BoxParams>: mul @Any,
+        #[role = "text"]
+        Label<::common::BoxParams>: mul Text,
+        Panel<::common::BoxParams> {
+            body: mul @Any,
+            footer: mul @Any,
+        },
+        #[role = "image"]
+        Image<::common::ImageParams>,
+        Card<::common::BoxParams> {
+            title: opt Label,
+            action: Button,
+        },
+        Frame<::common::BoxParams>: @Any,
+        Tip: opt Label,
+    }
+    groups {
+        Any: Group Dialog Button Label Panel Image Card Frame Tip,
+    }
+}
+
+/// Path of the node reached from the root by the `children` keys `keys`.
+pub fn path(keys: &[&'static str]) -> Path {
+    Some(PathEntry::Empty)
+        .into_iter()
+        .chain(keys.iter().map(|k| PathEntry::Key(Key::from(*k))))
+        .collect()
+}
@@ -0,0 +1,56 @@
+extern crate vtree;
+
+use vtree::diff::{Context, Differ, PathEntry, PathFrame, SimplePathFrame};
+use vtree::key::Key;
+
+/// Records the nodes passed to the callbacks, keeping the default `diff_replaced`.
+#[derive(Debug, Default)]
+struct Recorder(Vec<String>);
+
+impl Differ<(), &'static str> for Recorder {
+    fn diff_added(&mut self, _ctx: &mut Context<(), &'static str>, curr: &PathFrame<&'static str>) {
+        self.0.push(format!("added {}", curr.node()));
+    }
+
+    fn diff_removed(&mut self, _ctx: &mut Context<(), &'static str>,
+                    last: &PathFrame<&'static str>) {
+        self.0.push(format!("removed {}", last.node()));
+    }
+
+    fn diff_params_changed(&mut self, _ctx: &mut Context<(), &'static str>,
+                           _curr: &PathFrame<&'static str>, _last: &PathFrame<&'static str>) {
+    }
+
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(&mut self,
+                                                          _ctx: &mut Context<(), &'static str>,
+                                                          _parent: &PathFrame<&'static str>,
+                                                          _slot: Option<&'static str>,
+                                                          _indices: I) {
+    }
+}
+
+#[test]
+fn default_diff_replaced() {
+    let (curr, last) = ("curr", "last");
+    let mut recorder = Recorder::default();
+    recorder.diff_replaced(&mut Context::new(()), &PathFrame::new(&curr), &PathFrame::new(&last));
+    assert_eq!(recorder.0, vec!["removed last", "added curr"]);
+}
+
+#[test]
+fn to_path_is_root_first() {
+    let nodes = ["root", "child", "grandchild"];
+    let root = PathFrame::new(&nodes[0]);
+    let child = root.add_key(Key::from(1u64), 0, &nodes[1]);
+    let grandchild = child.add_slot("body", &nodes[2]);
+    let expected: Vec<_> = vec![PathEntry::Empty, PathEntry::Key(Key::from(1u64)),
+                                PathEntry::Slot("body")];
+    assert_eq!(grandchild.to_path().iter().cloned().collect::<Vec<_>>(), expected);
+    assert_eq!(grandchild.to_path().to_string(), ".u1.body");
+    assert_eq!(grandchild.to_path().parent(), Some(child.to_path()));
+
+    let root = SimplePathFrame::new();
+    let child = root.add_key(Key::from(1u64));
+    let grandchild = child.add_slot("body");
+    assert_eq!(grandchild.to_path().iter().cloned().collect::<Vec<_>>(), expected);
+}
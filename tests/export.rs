@@ -0,0 +1,35 @@
+extern crate vtree;
+#[macro_use]
+extern crate export_kit;
+
+use groups::{AllNodes, Content, Item};
+use vtree::template;
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+// `define_nodes` isn't in scope, the exported macro reaches it through `export_kit`
+kit_nodes!{
+    nodes {
+        Page: mul @Content,
+    }
+    groups {
+        Content: @Item Page,
+    }
+}
+
+#[test]
+fn nodes_exported_from_another_crate() {
+    // the kit's nodes are declared anew here, sharing the kit's params types
+    let button = Button::builder().params().set_label("ok".into()).build().build();
+    let params: export_kit::ButtonParams = button.params.clone();
+    assert_eq!(params.label, "ok");
+    let _: Item = Stack::builder().build().into();
+    let _: Content = Page::builder().build().into();
+    let _: Content = button.into();
+
+    match template::from_str::<AllNodes>(r#"Page { Stack { Button label="a" / } }"#).unwrap() {
+        AllNodes::Page(ref page) => assert_eq!(page.children.iter().count(), 1),
+        ref node => panic!("expected a page, got {:?}", node),
+    }
+}
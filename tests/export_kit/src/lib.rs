@@ -0,0 +1,29 @@
+//! Node kit exporting its nodes, used by the `export` test of `vtree` from another crate.
+
+extern crate vtree;
+extern crate vtree_macros;
+
+// the exported `kit_nodes!` invokes `$crate::define_nodes!`
+pub use vtree_macros::define_nodes;
+use vtree_macros::define_params;
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+define_params!{
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct ButtonParams {
+        pub label: String,
+    }
+}
+
+define_nodes!{
+    export kit_nodes(::export_kit);
+    nodes {
+        Stack: mul @Item,
+        Button<::ButtonParams>,
+    }
+    groups {
+        Item: Stack Button,
+    }
+}
@@ -0,0 +1,128 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use common::{path, Button, Group};
+use common::groups::AllNodes;
+use vtree::diff::{Context, PathFrame};
+use vtree::focus::Focus;
+use vtree_markup::markup;
+
+#[test]
+fn focus_tab_order() {
+    let root: AllNodes = markup!(Group {
+        Button@"a" focusable=true { Button@"b" focusable=true / }
+        Button@"c" /
+        Button@"d" focusable=true /
+    });
+
+    let mut focus = Focus::new();
+    focus.update(&root);
+    assert_eq!(focus.order(), &[path(&["a"]), path(&["a", "b"]), path(&["d"])]);
+    assert_eq!(focus.focused(), None);
+
+    assert_eq!(focus.focus_next(), Some(&path(&["a"])));
+    assert_eq!(focus.focus_next(), Some(&path(&["a", "b"])));
+    assert_eq!(focus.focus_next(), Some(&path(&["d"])));
+    assert_eq!(focus.focus_next(), Some(&path(&["a"])));
+    assert_eq!(focus.focus_prev(), Some(&path(&["d"])));
+
+    assert!(!focus.focus(&path(&["c"])));
+    assert!(focus.focus(&path(&["a", "b"])));
+    assert!(focus.is_focused(&path(&["a", "b"])));
+}
+
+#[test]
+fn focus_survives_reorder() {
+    let last: AllNodes = markup!(Group {
+        Button@"a" focusable=true /
+        Button@"b" focusable=true /
+    });
+    let curr: AllNodes = markup!(Group {
+        Button@"b" focusable=true /
+        Button@"a" focusable=true /
+    });
+
+    let mut focus = Focus::new();
+    focus.update(&last);
+    focus.focus(&path(&["b"]));
+    let mut ctx = Context::new(());
+    AllNodes::diff(&PathFrame::new(&curr), &PathFrame::new(&last), &mut ctx, &mut focus);
+    focus.update(&curr);
+    assert_eq!(focus.focused(), Some(&path(&["b"])));
+    assert_eq!(focus.focus_next(), Some(&path(&["a"])));
+}
+
+#[test]
+fn focus_moves_to_neighbour_on_remove() {
+    let last: AllNodes = markup!(Group {
+        Button@"a" focusable=true /
+        Button@"b" focusable=true /
+        Button@"c" focusable=true /
+    });
+    let curr: AllNodes = markup!(Group {
+        Button@"a" focusable=true /
+        Button@"c" focusable=true /
+    });
+
+    let mut focus = Focus::new();
+    focus.update(&last);
+    focus.focus(&path(&["b"]));
+
+    let mut ctx = Context::new(());
+    AllNodes::diff(&PathFrame::new(&curr), &PathFrame::new(&last), &mut ctx, &mut focus);
+    focus.update(&curr);
+    assert_eq!(focus.focused(), Some(&path(&["c"])));
+
+    let last = curr;
+    let curr: AllNodes = markup!(Group { Button@"a" focusable=true / });
+    AllNodes::diff(&PathFrame::new(&curr), &PathFrame::new(&last), &mut ctx, &mut focus);
+    focus.update(&curr);
+    assert_eq!(focus.focused(), Some(&path(&["a"])));
+}
+
+#[test]
+fn focus_follows_params() {
+    let last: AllNodes = markup!(Group {
+        Button@"a" focusable=true /
+        Button@"b" focusable=true /
+    });
+    let curr: AllNodes = markup!(Group {
+        Button@"a" focusable=true /
+        Button@"b" /
+    });
+
+    let mut focus = Focus::new();
+    focus.update(&last);
+    focus.focus(&path(&["b"]));
+    let mut ctx = Context::new(());
+    AllNodes::diff(&PathFrame::new(&curr), &PathFrame::new(&last), &mut ctx, &mut focus);
+    focus.update(&curr);
+    assert_eq!(focus.order(), &[path(&["a"])]);
+    assert_eq!(focus.focused(), Some(&path(&["a"])));
+}
+
+#[test]
+fn focus_next_after_remove_is_kept() {
+    let last: AllNodes = markup!(Group {
+        Button@"a" focusable=true /
+        Button@"b" focusable=true /
+        Button@"c" focusable=true /
+    });
+    let curr: AllNodes = markup!(Group {
+        Button@"b" focusable=true /
+        Button@"c" focusable=true /
+    });
+
+    let mut focus = Focus::new();
+    focus.update(&last);
+    focus.focus(&path(&["a"]));
+    let mut ctx = Context::new(());
+    AllNodes::diff(&PathFrame::new(&curr), &PathFrame::new(&last), &mut ctx, &mut focus);
+    // moving focus before the update replaces the lost focus
+    assert_eq!(focus.focus_next(), Some(&path(&["b"])));
+    focus.update(&curr);
+    assert_eq!(focus.focused(), Some(&path(&["b"])));
+}
@@ -0,0 +1,52 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+use std::fmt::Debug;
+use groups::AllNodes;
+use vtree::template;
+use vtree_macros::{define_nodes, define_params};
+use vtree_markup::markup;
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+define_params!{
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct ListParams<T: Clone + Debug + PartialEq> {
+        pub items: Vec<T>,
+        pub selected: Option<usize>,
+    }
+}
+
+define_nodes!{
+    nodes {
+        IntList<::ListParams<i64>>,
+        TextList<::ListParams<String>>: mul @Lists,
+    }
+    groups {
+        Lists: IntList TextList,
+    }
+}
+
+#[test]
+fn nodes_with_instantiated_generic_params() {
+    let ints = IntList::builder().params().set_items(vec![1, 2]).set_selected(Some(1)).build().build();
+    assert_eq!(ints.params.items, vec![1, 2]);
+    assert_eq!(IntList::builder().build().params, ListParams::<i64>::default());
+    let texts: TextList = markup!(TextList items=(vec!["a".to_string()]) {
+        IntList items=(vec![3]) /
+    });
+    assert_eq!(texts.params.items, vec!["a".to_string()]);
+    assert_eq!(texts.children.iter().count(), 1);
+}
+
+#[test]
+fn template_with_instantiated_generic_params() {
+    match template::from_str::<AllNodes>("IntList selected=1 /").unwrap() {
+        AllNodes::IntList(ref list) => assert_eq!(list.params.selected, Some(1)),
+        ref node => panic!("expected an int list, got {:?}", node),
+    }
+    let err = template::from_str::<AllNodes>("IntList items=1 /").unwrap_err();
+    assert_eq!(err.message, "`items`: can't be set from a template, got `1`");
+}
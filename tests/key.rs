@@ -0,0 +1,30 @@
+extern crate vtree;
+
+use std::collections::HashMap;
+use vtree::child::Multi;
+use vtree::key::Key;
+
+#[test]
+fn auto_key() {
+    assert_eq!(Key::Auto(1), Key::Auto(1));
+    assert!(Key::Auto(1) != Key::U64(1));
+    assert!(Key::Auto(1) != Key::from(1i64));
+    assert_eq!(Key::Auto(1).to_string(), "a1");
+}
+
+#[test]
+fn str_and_string_keys_hash_alike() {
+    let mut keys = HashMap::new();
+    keys.insert(Key::Str("a"), 1);
+    assert_eq!(keys.get(&Key::from("a".to_string())), Some(&1));
+    assert_eq!(keys.get(&Key::Str("b")), None);
+    assert_eq!(keys.get(&Key::U64(1)), None);
+}
+
+#[test]
+#[should_panic(expected = "multiple nodes using same key")]
+fn str_and_string_keys_collide_in_multi() {
+    let mut multi: Multi<(), ()> = Multi::new();
+    multi.push(Key::Str("a"), ());
+    multi.push(Key::from("a".to_string()), ());
+}
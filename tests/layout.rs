@@ -0,0 +1,128 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use common::{path, Group};
+use common::groups::AllNodes;
+use vtree::diff::{Context, PathFrame};
+use vtree::layout::{Direction, Edges, Layout, Rect, Style};
+use vtree::node::NodeChildren;
+use vtree_markup::markup;
+
+#[test]
+fn layout_grow_and_padding() {
+    let root: AllNodes = markup!(Group style=(Style {
+        direction: Direction::Row,
+        padding: Edges::all(10.0),
+        ..Style::default()
+    }) {
+        Group@"a" style=(Style { width: Some(20.0), ..Style::default() }) /
+        Group@"b" style=(Style { grow: 1.0, ..Style::default() }) /
+        Group@"c" style=(Style { grow: 3.0, height: Some(5.0), ..Style::default() }) /
+    });
+
+    let mut layout = Layout::new();
+    layout.compute(&root, Rect::new(0.0, 0.0, 120.0, 50.0));
+
+    assert_eq!(layout.rect(&path(&[])), Some(&Rect::new(0.0, 0.0, 120.0, 50.0)));
+    assert_eq!(layout.rect(&path(&["a"])), Some(&Rect::new(10.0, 10.0, 20.0, 30.0)));
+    assert_eq!(layout.rect(&path(&["b"])), Some(&Rect::new(30.0, 10.0, 20.0, 30.0)));
+    assert_eq!(layout.rect(&path(&["c"])), Some(&Rect::new(50.0, 10.0, 60.0, 5.0)));
+}
+
+#[test]
+fn layout_shrink() {
+    let root: AllNodes = markup!(Group {
+        Group@"a" style=(Style { height: Some(60.0), ..Style::default() }) /
+        Group@"b" style=(Style { height: Some(20.0), shrink: 0.0, ..Style::default() }) /
+        Group@"c" style=(Style { height: Some(40.0), ..Style::default() }) /
+    });
+
+    let mut layout = Layout::new();
+    layout.compute(&root, Rect::new(0.0, 0.0, 10.0, 90.0));
+
+    assert_eq!(layout.rect(&path(&["a"])), Some(&Rect::new(0.0, 0.0, 10.0, 42.0)));
+    assert_eq!(layout.rect(&path(&["b"])), Some(&Rect::new(0.0, 42.0, 10.0, 20.0)));
+    assert_eq!(layout.rect(&path(&["c"])), Some(&Rect::new(0.0, 62.0, 10.0, 28.0)));
+}
+
+#[test]
+fn layout_invalidates_ancestors_only() {
+    let last: AllNodes = markup!(Group {
+        Group@"a" { Group@"a" / }
+        Group@"b" /
+    });
+    let curr: AllNodes = markup!(Group {
+        Group@"a" { Group@"a" style=(Style { grow: 1.0, ..Style::default() }) / }
+        Group@"b" /
+    });
+
+    let mut layout = Layout::new();
+    layout.compute(&last, Rect::new(0.0, 0.0, 10.0, 10.0));
+
+    let mut ctx = Context::new(());
+    AllNodes::diff(&PathFrame::new(&curr), &PathFrame::new(&last), &mut ctx, &mut layout);
+
+    assert!(layout.is_dirty(&path(&["a", "a"])));
+    assert!(layout.is_dirty(&path(&["a"])));
+    assert!(layout.is_dirty(&path(&[])));
+    assert!(!layout.is_dirty(&path(&["b"])));
+
+    let last = curr;
+    let curr: AllNodes = markup!(Group { Group@"b" / });
+    AllNodes::diff(&PathFrame::new(&curr), &PathFrame::new(&last), &mut ctx, &mut layout);
+    assert_eq!(layout.rect(&path(&["a"])), None);
+    assert_eq!(layout.rect(&path(&["a", "a"])), None);
+    assert!(layout.rect(&path(&["b"])).is_some());
+}
+
+#[test]
+fn hit_test() {
+    let root: AllNodes = markup!(Group {
+        Group@"a" style=(Style { height: Some(20.0), ..Style::default() }) {
+            Group@"a" style=(Style { height: Some(10.0), ..Style::default() }) /
+        }
+        Group@"b" style=(Style { height: Some(20.0), ..Style::default() }) /
+    });
+
+    let mut layout = Layout::new();
+    layout.compute(&root, Rect::new(0.0, 0.0, 10.0, 50.0));
+
+    assert_eq!(layout.hit_test(&root, 5.0, 5.0), Some(path(&["a", "a"])));
+    assert_eq!(layout.hit_test(&root, 5.0, 15.0), Some(path(&["a"])));
+    assert_eq!(layout.hit_test(&root, 5.0, 25.0), Some(path(&["b"])));
+    assert_eq!(layout.hit_test(&root, 5.0, 45.0), Some(path(&[])));
+    assert_eq!(layout.hit_test(&root, 15.0, 5.0), None);
+
+    let hit = layout.hit_test(&root, 5.0, 25.0).unwrap();
+    match *root.find(&hit).unwrap() {
+        AllNodes::Group(ref group) => assert_eq!(group.children.iter().count(), 0),
+        ref node => panic!("expected a group, got {:?}", node),
+    }
+    assert!(root.find(&path(&["c"])).is_none());
+}
+
+#[test]
+fn hit_test_overflow() {
+    let overflowing = |clip: bool| -> AllNodes {
+        markup!(Group {
+            Group@"a" style=(Style { height: Some(10.0), clip: clip, ..Style::default() }) {
+                Group@"a" style=(Style { height: Some(30.0), shrink: 0.0, ..Style::default() }) /
+            }
+        })
+    };
+
+    let root = overflowing(false);
+    let mut layout = Layout::new();
+    layout.compute(&root, Rect::new(0.0, 0.0, 10.0, 50.0));
+    assert_eq!(layout.rect(&path(&["a", "a"])), Some(&Rect::new(0.0, 0.0, 10.0, 30.0)));
+    assert_eq!(layout.hit_test(&root, 5.0, 20.0), Some(path(&["a", "a"])));
+
+    let root = overflowing(true);
+    let mut layout = Layout::new();
+    layout.compute(&root, Rect::new(0.0, 0.0, 10.0, 50.0));
+    assert_eq!(layout.hit_test(&root, 5.0, 20.0), Some(path(&[])));
+    assert_eq!(layout.hit_test(&root, 5.0, 5.0), Some(path(&["a", "a"])));
+}
@@ -0,0 +1,119 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use common::{Button, Card, Dialog, Group, Label, Panel};
+use common::groups::AllNodes;
+use vtree::key::Key;
+use vtree::node::NodeChildren;
+use vtree_markup::markup;
+
+/// Slots and keys of the children of `node`, in order.
+fn child_keys(node: &AllNodes) -> Vec<String> {
+    let mut keys = Vec::new();
+    node.for_each_child(|entry, _| keys.push(entry.to_path_entry().to_string()));
+    keys
+}
+
+#[test]
+fn named_slots() {
+    let panel: AllNodes = markup!(Panel label="p" {
+        body: {
+            Group@"a" /
+            Button@"b" /
+        }
+        footer: Label { "ok" }
+    });
+    match panel {
+        AllNodes::Panel(ref panel) => {
+            assert_eq!(panel.params.label, "p");
+            let body: Vec<&Key> = panel.body.iter().map(|(key, _)| key).collect();
+            assert_eq!(body, vec![&Key::from("a"), &Key::from("b")]);
+            assert_eq!(panel.footer.iter().count(), 1);
+        }
+        ref node => panic!("expected a panel, got {:?}", node),
+    }
+    assert_eq!(child_keys(&panel), child_keys(&Panel::builder()
+        .params().set_label("p".into()).build()
+        .body().add("a".into(), Group::builder().build().into())
+        .add("b".into(), Button::builder().build().into()).build()
+        .footer().add(Key::Auto(0), markup!(Label { "ok" })).build()
+        .build()
+        .into()));
+}
+
+#[test]
+fn named_single_and_option_slots() {
+    let card = |title: bool| -> AllNodes {
+        markup!(Card {
+            title: { if title { Label { "title" } } }
+            action: Button label="go" /
+        })
+    };
+    match card(true) {
+        AllNodes::Card(ref card) => {
+            assert!(card.title.is_some());
+            match *card.action {
+                AllNodes::Button(ref button) => assert_eq!(button.params.label, "go"),
+                ref node => panic!("expected a button, got {:?}", node),
+            }
+        }
+        ref node => panic!("expected a card, got {:?}", node),
+    }
+    match card(false) {
+        AllNodes::Card(ref card) => assert!(card.title.is_none()),
+        ref node => panic!("expected a card, got {:?}", node),
+    }
+}
+
+#[test]
+fn keys_within_for() {
+    let items = vec![(1u64, "a"), (2, "b")];
+    match markup!(Group {
+        for &(id, label) in &items @(id) {
+            Label { "item" }
+            Button@(label) {
+                Label@0 { "label" }
+            }
+        }
+    }) {
+        AllNodes::Group(ref group) => {
+            let keys: Vec<&Key> = group.children.iter().map(|(key, _)| key).collect();
+            assert_eq!(keys, vec![&Key::U64(1), &Key::from("a"), &Key::U64(2), &Key::from("b")]);
+        }
+        ref node => panic!("expected a group, got {:?}", node),
+    }
+}
+
+#[test]
+fn auto_keys_across_if_and_match() {
+    let tree = |flag: bool, n: u8| -> Vec<Key> {
+        match markup!(Group {
+            Label { "first" }
+            if flag {
+                Button /
+            } else {
+                Dialog /
+                Label { "else" }
+            }
+            match n {
+                0 => Group /,
+                1 => { Button / }
+                _ => {}
+            }
+            Label { "last" }
+        }) {
+            AllNodes::Group(ref group) => group.children.iter().map(|(key, _)| key.clone()).collect(),
+            ref node => panic!("expected a group, got {:?}", node),
+        }
+    };
+    // every unkeyed node of the template has its own index, whichever branches are taken
+    assert_eq!(tree(true, 0), vec![Key::Auto(0), Key::Auto(1), Key::Auto(4), Key::Auto(6)]);
+    assert_eq!(tree(false, 1),
+               vec![Key::Auto(0), Key::Auto(2), Key::Auto(3), Key::Auto(5), Key::Auto(6)]);
+    assert_eq!(tree(true, 2), vec![Key::Auto(0), Key::Auto(1), Key::Auto(6)]);
+    // positional keys never equal user keys
+    assert!(Key::Auto(0) != Key::U64(0));
+}
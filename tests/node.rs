@@ -1,6 +1,6 @@
 extern crate vtree;
 
-use vtree::node::TypeMap;
+use vtree::node::{self, DefaultParams, ParamsError, TypeMap};
 
 #[test]
 fn type_map() {
@@ -30,3 +30,35 @@ fn type_map() {
     assert_eq!(tm.remove::<String>(), Some("asd".to_string()));
     assert!(!tm.contains::<String>());
 }
+
+#[derive(Debug, PartialEq)]
+struct NoDefault;
+
+#[derive(Debug, PartialEq)]
+struct Defaulted(u8);
+
+impl DefaultParams for Defaulted {
+    fn default_params() -> Defaulted {
+        Defaulted(1)
+    }
+}
+
+#[test]
+fn default_params() {
+    assert_eq!(node::default_params::<Defaulted>(), Some(Defaulted(1)));
+    assert_eq!(node::default_params::<NoDefault>(), None);
+    // `Default` alone doesn't give a params type default params
+    assert_eq!(node::default_params::<String>(), None);
+}
+
+#[test]
+fn params_error_display() {
+    let mut err = ParamsError {
+        params: "RangeParams",
+        field: Some("min"),
+        message: "-1 is negative".to_string(),
+    };
+    assert_eq!(err.to_string(), "invalid `RangeParams::min`: -1 is negative");
+    err.field = None;
+    assert_eq!(err.to_string(), "invalid `RangeParams`: -1 is negative");
+}
@@ -0,0 +1,55 @@
+extern crate vtree;
+extern crate vtree_macros;
+
+// the exported `kit_nodes!` invokes `$crate::define_nodes!`
+use vtree_macros::define_nodes;
+
+#[macro_use]
+mod kit {
+    use vtree_macros::define_nodes;
+
+    #[derive(Debug)]
+    pub enum AllEvent {}
+
+    define_nodes!{
+        export kit_nodes;
+        nodes {
+            Row: mul @Inline,
+            Text2: mul @Block,
+            Leaf,
+        }
+        groups(fixpoint) {
+            Inline: Text2 @Block,
+            Block: Row @Inline,
+        }
+    }
+}
+
+mod app {
+    #[derive(Debug)]
+    pub enum AllEvent {}
+
+    kit_nodes!{
+        nodes {
+            Page: mul @Content,
+        }
+        groups {
+            Content: @Inline Leaf Page,
+            Plain: @Content -Page,
+        }
+    }
+}
+
+#[test]
+fn fixpoint_section_with_plain_section() {
+    use kit::groups::{Block, Inline};
+    let _: Inline = kit::Row::new(Default::default()).into();
+    let _: Block = kit::Text2::new(Default::default()).into();
+
+    use app::groups::{Content, Plain};
+    let _: Content = app::Row::new(Default::default()).into();
+    let _: Content = app::Leaf::new().into();
+    let _: Content = app::Page::new(Default::default()).into();
+    let _: Plain = app::Text2::new(Default::default()).into();
+    let _: Plain = app::Leaf::new().into();
+}
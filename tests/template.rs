@@ -0,0 +1,385 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use vtree::diff::{Context, Differ, PathFrame, PathIndexEntry, SimplePathFrame};
+use vtree::key::Key;
+use vtree::node::NodeChildren;
+use vtree::template::{self, FromTemplate, FromValue, Markup, MarkupHead, MarkupNode, Node,
+                      Param, Position, Reloader, TemplateError, TemplateRoot, ToMarkupValue,
+                      Value};
+use vtree_markup::markup;
+
+/// Childless node diffed by name and params.
+#[derive(Debug, Clone, PartialEq)]
+struct Leaf {
+    name: String,
+    params: Vec<Param>,
+}
+
+impl FromTemplate for Leaf {
+    fn from_template(node: &Node) -> Result<Leaf, TemplateError> {
+        match node.name() {
+            Some(name) => {
+                Ok(Leaf {
+                    name: name.to_string(),
+                    params: node.params().to_vec(),
+                })
+            }
+            None => Err(node.error("expected a node")),
+        }
+    }
+}
+
+impl TemplateRoot for Leaf {
+    fn expand_widgets(_curr: &mut Leaf, _last: Option<&Leaf>, _path: &SimplePathFrame) {}
+
+    fn diff<CTX, D>(curr: &PathFrame<Leaf>, last: &PathFrame<Leaf>, ctx: &mut Context<CTX, Leaf>,
+                    differ: &mut D)
+        where D: Differ<CTX, Leaf>
+    {
+        if curr.node().name != last.node().name {
+            differ.diff_replaced(ctx, curr, last);
+        } else if curr.node().params != last.node().params {
+            differ.diff_params_changed(ctx, curr, last);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Recorder(Vec<&'static str>);
+
+impl<AN> Differ<(), AN> for Recorder {
+    fn diff_added(&mut self, _ctx: &mut Context<(), AN>, _curr: &PathFrame<AN>) {
+        self.0.push("added");
+    }
+
+    fn diff_removed(&mut self, _ctx: &mut Context<(), AN>, _last: &PathFrame<AN>) {
+        self.0.push("removed");
+    }
+
+    fn diff_replaced(&mut self, _ctx: &mut Context<(), AN>, _curr: &PathFrame<AN>,
+                     _last: &PathFrame<AN>) {
+        self.0.push("replaced");
+    }
+
+    fn diff_params_changed(&mut self, _ctx: &mut Context<(), AN>, _curr: &PathFrame<AN>,
+                           _last: &PathFrame<AN>) {
+        self.0.push("params_changed");
+    }
+
+    fn diff_reordered<I: Iterator<Item = (usize, usize)>>(&mut self,
+                                                          _ctx: &mut Context<(), AN>,
+                                                          _parent: &PathFrame<AN>,
+                                                          _slot: Option<&'static str>,
+                                                          _indices: I) {
+    }
+}
+
+fn pos(line: usize, column: usize) -> Position {
+    Position {
+        line: line,
+        column: column,
+    }
+}
+
+#[test]
+fn template_parse() {
+    let node = template::parse(r#"
+        // comment
+        A@"a" s="x\ty" n=-3 f=1.5 c='\'' on? {
+            B@-1 /
+            Label "text"@2
+        }
+    "#).unwrap();
+
+    assert_eq!(node.name(), Some("A"));
+    assert_eq!(node.key(), Some(&Key::from("a")));
+    assert_eq!(node.position(), pos(3, 9));
+    let params: Vec<_> = node.params().iter().map(|p| (p.name.as_str(), p.value.clone())).collect();
+    assert_eq!(params, vec![
+        ("s", Value::String("x\ty".into())),
+        ("n", Value::Int(-3)),
+        ("f", Value::Float(1.5)),
+        ("c", Value::Char('\'')),
+        ("on", Value::Bool(true)),
+    ]);
+    assert_eq!(node.params()[1], Param {
+        name: "n".into(),
+        value: Value::Int(-3),
+        position: pos(3, 24),
+    });
+
+    let children = node.children();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0].key(), Some(&Key::I64(-1)));
+    assert_eq!(children[1].children(), &[Node::Text {
+        text: "text".into(),
+        key: Some(Key::U64(2)),
+        position: pos(5, 19),
+    }]);
+}
+
+#[test]
+fn template_parse_slots() {
+    let node = template::parse("A {\n  B /\n  s: { C@1 / D / }\n  t: E /\n}").unwrap();
+    assert_eq!(node.children().len(), 1);
+    let slots: Vec<_> = node.slots()
+        .iter()
+        .map(|slot| (slot.name.as_str(), slot.children.len(), slot.position))
+        .collect();
+    assert_eq!(slots, vec![("s", 2, pos(3, 3)), ("t", 1, pos(4, 3))]);
+    let keys: Vec<_> = node.keyed_slot_children("s").map(|(key, _)| key).collect();
+    assert_eq!(keys, vec![Key::U64(1), Key::Auto(0)]);
+    assert!(node.slot_children("u").is_empty());
+
+    let err = template::parse("A {\n  s: B /\n  s: C /\n}").unwrap_err();
+    assert_eq!(err, TemplateError::new("duplicate slot `s`", pos(3, 3)));
+}
+
+#[test]
+fn template_named_slots() {
+    use common::groups::AllNodes;
+    let panel = template::from_str::<AllNodes>(r#"
+        Panel label="p" {
+            body: {
+                Group@"a" /
+                Button@"b" /
+            }
+            footer: Label { "ok" }
+        }
+    "#).unwrap();
+    match panel {
+        AllNodes::Panel(ref panel) => {
+            assert_eq!(panel.params.label, "p");
+            let body: Vec<&Key> = panel.body.iter().map(|(key, _)| key).collect();
+            assert_eq!(body, vec![&Key::from("a"), &Key::from("b")]);
+            assert_eq!(panel.footer.iter().count(), 1);
+        }
+        ref node => panic!("expected a panel, got {:?}", node),
+    }
+    match template::from_str::<AllNodes>("Card { action: Button label=\"go\" / }").unwrap() {
+        AllNodes::Card(ref card) => {
+            assert!(card.title.is_none());
+            match *card.action {
+                AllNodes::Button(ref button) => assert_eq!(button.params.label, "go"),
+                ref node => panic!("expected a button, got {:?}", node),
+            }
+        }
+        ref node => panic!("expected a card, got {:?}", node),
+    }
+
+    let err = |source| template::from_str::<AllNodes>(source).unwrap_err();
+    assert_eq!(err("Card {\n  title: Label /\n}"),
+               TemplateError::new("`Card` needs exactly one child in slot `action`", pos(1, 1)));
+    assert_eq!(err("Card {\n  action: { Button / Button / }\n}"),
+               TemplateError::new("`Card` needs exactly one child in slot `action`", pos(1, 1)));
+    assert_eq!(err("Card {\n  title: { Label / Label / }\n  action: Button /\n}"),
+               TemplateError::new("`Card` takes at most one child in slot `title`", pos(2, 20)));
+    assert_eq!(err("Card {\n  action: Label /\n}"),
+               TemplateError::new("expected `Button`, got `Label`", pos(2, 11)));
+    assert_eq!(err("Panel {\n  header: Label /\n}"),
+               TemplateError::new("`Panel` has no slot `header`", pos(2, 3)));
+    assert_eq!(err("Panel {\n  Label /\n}"),
+               TemplateError::new("`Panel` can't have children outside of its slots `body`, \
+                                   `footer`",
+                                  pos(2, 3)));
+    assert_eq!(err("Panel {\n  body: { Label@1 / Button@1 / }\n}"),
+               TemplateError::new("duplicate key in the slot `body` of `Panel`", pos(2, 21)));
+}
+
+#[test]
+fn template_keyed_children() {
+    let node = template::parse("A { B/ C@1 / D/ }").unwrap();
+    let keys: Vec<_> = node.keyed_children().map(|(key, _)| key).collect();
+    assert_eq!(keys, vec![Key::Auto(0), Key::U64(1), Key::Auto(1)]);
+}
+
+#[test]
+fn template_parse_errors() {
+    let err = |source| template::parse(source).unwrap_err();
+    assert_eq!(err("A"), TemplateError::new("unexpected end of input, expected `/`, `{` or a \
+                                             child after `A`", pos(1, 2)));
+    assert_eq!(err("A {\n  B/"), TemplateError::new("unclosed `{` of `A`", pos(2, 5)));
+    assert_eq!(err("A x=y /"), TemplateError::new("expected a value, found `y`", pos(1, 5)));
+    assert_eq!(err("A@1.5 /"), TemplateError::new("`1.5` can't be used as a key", pos(1, 6)));
+    assert_eq!(err("A / B /"), TemplateError::new("unexpected input after the root node",
+                                                  pos(1, 5)));
+    assert_eq!(err("\"a"), TemplateError::new("unterminated string", pos(1, 1)));
+}
+
+#[test]
+fn template_duplicate_keys() {
+    use common::groups::AllNodes;
+    let err = template::from_str::<AllNodes>("Group {\n  Label@1 /\n  Button@1 /\n}")
+        .unwrap_err();
+    assert_eq!(err, TemplateError::new("duplicate key in the children of `Group`", pos(3, 3)));
+    // positional keys don't collide with integer keys
+    assert!(template::from_str::<AllNodes>("Group { Label@0 / Button / }").is_ok());
+}
+
+#[test]
+fn template_required_params() {
+    use common::groups::AllNodes;
+    match template::from_str::<AllNodes>(r#"Image src="a.png" /"#).unwrap() {
+        AllNodes::Image(ref image) => {
+            assert_eq!((&image.params.src[..], &image.params.alt[..]), ("a.png", ""))
+        }
+        ref node => panic!("expected an image, got {:?}", node),
+    }
+    let err = template::from_str::<AllNodes>("Group {\n  Image alt=\"a\" /\n}").unwrap_err();
+    assert_eq!(err,
+               TemplateError::new("missing the required field `ImageParams::src`", pos(2, 3)));
+}
+
+#[test]
+fn template_from_value() {
+    assert_eq!(u8::from_value(&Value::Int(255)), Ok(255));
+    assert_eq!(u8::from_value(&Value::Int(256)), Err("256 is out of range for `u8`".into()));
+    assert_eq!(u64::from_value(&Value::Int(-1)), Err("-1 is out of range for `u64`".into()));
+    assert_eq!(i64::from_value(&Value::Int(-1)), Ok(-1));
+    assert_eq!(f32::from_value(&Value::Int(2)), Ok(2.0));
+    assert_eq!(Option::<String>::from_value(&Value::Char('c')), Ok(Some("c".into())));
+    assert_eq!(bool::from_value(&Value::Int(1)), Err("expected a bool, got `1`".into()));
+    assert_eq!(<Vec<u8>>::from_value(&Value::Int(1)),
+               Err("can't be set from a template, got `1`".into()));
+}
+
+#[test]
+fn template_reload() {
+    let mut reloader = Reloader::<Leaf>::new();
+    let mut ctx = Context::new(());
+    let mut reload = |source| {
+        let mut recorder = Recorder::default();
+        let name = reloader.reload(source, &mut ctx, &mut recorder).map(|leaf| leaf.name.clone());
+        (name, recorder.0)
+    };
+
+    assert_eq!(reload("A x=1 /"), (Ok("A".into()), vec!["added"]));
+    assert_eq!(reload("A x=1 /"), (Ok("A".into()), vec![]));
+    assert_eq!(reload("A x=2 /"), (Ok("A".into()), vec!["params_changed"]));
+    // a broken source keeps the last tree and reports nothing
+    assert!(reload("A x= /").1.is_empty());
+    assert_eq!(reload("A x=2 /"), (Ok("A".into()), vec![]));
+    assert_eq!(reload("B /"), (Ok("B".into()), vec!["replaced"]));
+}
+
+#[test]
+fn template_reload_duplicate_keys() {
+    use common::groups::AllNodes;
+    let mut reloader = Reloader::<AllNodes>::new();
+    let mut ctx = Context::new(());
+    let mut reload = |source| {
+        let mut recorder = Recorder::default();
+        let result = reloader.reload(source, &mut ctx, &mut recorder).map(|_| ());
+        (result, recorder.0)
+    };
+
+    assert_eq!(reload("Group { Label@1 / }"), (Ok(()), vec!["added"]));
+    // the duplicate key is reported instead of panicking, the last tree is kept
+    assert_eq!(reload("Group { Label@1 /\n  Button@1 / }"),
+               (Err(TemplateError::new("duplicate key in the children of `Group`", pos(2, 3))),
+                vec![]));
+    assert_eq!(reload("Group { Label@1 / }"), (Ok(()), vec![]));
+    assert_eq!(reload("Group { Label@1 / Button@2 / }"), (Ok(()), vec!["added"]));
+}
+
+#[derive(Debug)]
+enum Tree {
+    Node(&'static str, Vec<(&'static str, String)>, Vec<(Key, Tree)>, Vec<(Key, Tree)>),
+    Text(&'static str),
+}
+
+impl NodeChildren for Tree {
+    fn for_each_child<'a, F>(&'a self, mut f: F)
+        where F: FnMut(PathIndexEntry, &'a Tree)
+    {
+        if let Tree::Node(_, _, ref children, ref footer) = *self {
+            for (index, &(ref key, ref node)) in children.iter().enumerate() {
+                f(PathIndexEntry::Key(key.clone(), index), node);
+            }
+            for (index, &(ref key, ref node)) in footer.iter().enumerate() {
+                f(PathIndexEntry::SlotKey("footer", key.clone(), index), node);
+            }
+        }
+    }
+}
+
+impl MarkupNode for Tree {
+    fn markup_head(&self) -> MarkupHead {
+        match *self {
+            Tree::Node(name, ref params, ..) => {
+                MarkupHead::Node {
+                    name: name,
+                    params: params.clone(),
+                }
+            }
+            Tree::Text(text) => MarkupHead::Text(text),
+        }
+    }
+}
+
+#[test]
+fn template_markup() {
+    let leaf = |name| Tree::Node(name, vec![], vec![], vec![]);
+    let tree = Tree::Node("List", vec![("title", "\"a\"".into()), ("n", "-1".into())], vec![
+        (Key::Auto(0), leaf("Item")),
+        (Key::I64(-2), Tree::Node("Label", vec![], vec![(Key::from("t"), Tree::Text("x\n"))],
+                                  vec![])),
+        (Key::I64(3), leaf("Item")),
+        (Key::Bytes(vec![1, 2].into()), leaf("Item")),
+    ], vec![
+        (Key::U64(1), leaf("Button")),
+    ]);
+
+    assert_eq!(Markup(&tree).to_string(), r#"List title="a" n=-1 {
+    Item /
+    Label@-2 {
+        "x\n"@"t"
+    }
+    Item@3i64 /
+    Item@(vec![1, 2]) /
+    footer: {
+        Button@1 /
+    }
+}
+"#);
+}
+
+#[test]
+fn template_markup_round_trip() {
+    use common::{Button, Card, Group, Label, Panel};
+    use common::groups::AllNodes;
+    let tree: AllNodes = markup!(Group label="g" {
+        Panel@"panel" label="p" focusable=true {
+            body: {
+                Label@1 { "body" }
+                Group@2 x=1.5 /
+            }
+            footer: Label { "footer" }
+        }
+        Card@"card" {
+            title: Label { "title" }
+            action: Button label="b" /
+        }
+        Label@"text" { "a\"b" }
+    });
+    let printed = tree.to_markup();
+    assert!(printed.contains("body: {") && printed.contains("action: {"), "{}", printed);
+    let parsed: AllNodes = template::from_str(&printed)
+        .unwrap_or_else(|err| panic!("{}\n{}", err, printed));
+    assert_eq!(parsed.to_markup(), printed);
+}
+
+#[test]
+fn template_to_markup_value() {
+    assert_eq!("a\"".to_markup_value(), Some(r#""a\"""#.into()));
+    assert_eq!(1.5f32.to_markup_value(), Some("1.5".into()));
+    assert_eq!(Some('c').to_markup_value(), Some("'c'".into()));
+    assert_eq!(None::<u8>.to_markup_value(), None);
+    assert_eq!(vec![1u8].to_markup_value(), Some("([1])".into()));
+    struct NoDebug;
+    assert_eq!(NoDebug.to_markup_value(), None);
+}
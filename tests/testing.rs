@@ -0,0 +1,275 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use common::{Button, Card, Frame, Group, Label, Panel, Tip};
+use common::groups::{AllNodes, Any};
+use std::env;
+use std::fs;
+use std::panic;
+use std::process;
+use vtree::child::Multi;
+use vtree::diff::{Context, Differ, PathFrame, PathIndexEntry, SimplePathFrame};
+use vtree::key::Key;
+use vtree::node::NodeChildren;
+use vtree::template::{self, ChildrenKind, ChildrenSchema, FromTemplate, MarkupHead, MarkupNode,
+                      Node, NodeSchema, ParamSchema, TemplateError, TemplateRoot, TemplateSchema,
+                      Value};
+use vtree::testing::{arbitrary_keys, arbitrary_tree_pair, assert_snapshot, check_diff,
+                     check_multi_diff, check_permutation, check_property, check_unique_keys,
+                     diff_snapshot, mutate_keys, text_diff, tree_snapshot, Rng};
+use vtree::widget::{Widget, WidgetData};
+use vtree_markup::markup;
+
+/// Tree loaded from templates, whose children are diffed by key like `define_nodes!` does.
+#[derive(Debug, Clone)]
+struct Tree {
+    name: &'static str,
+    params: Vec<(&'static str, String)>,
+    children: Multi<Tree, Tree>,
+}
+
+impl FromTemplate for Tree {
+    fn from_template(node: &Node) -> Result<Tree, TemplateError> {
+        let name = match node.name() {
+            Some("A") => "A",
+            Some("B") => "B",
+            _ => return Err(node.error("expected `A` or `B`")),
+        };
+        let mut children = Multi::new();
+        for (key, child) in node.keyed_children() {
+            children.push(key, Tree::from_template(child)?);
+        }
+        Ok(Tree {
+            name: name,
+            params: node.params().iter().map(|p| ("p", p.value.to_string())).collect(),
+            children: children,
+        })
+    }
+}
+
+impl NodeChildren for Tree {
+    fn for_each_child<'a, F>(&'a self, mut f: F)
+        where F: FnMut(PathIndexEntry, &'a Tree)
+    {
+        for (index, (key, node)) in self.children.iter().enumerate() {
+            f(PathIndexEntry::Key(key.clone(), index), node);
+        }
+    }
+}
+
+impl TemplateSchema for Tree {
+    fn template_schema() -> Vec<NodeSchema> {
+        ["A", "B"].iter().map(|&name| {
+            NodeSchema {
+                name: name,
+                params: vec![ParamSchema {
+                    name: "p",
+                    values: vec![Value::Int(1), Value::Int(2)],
+                    required: false,
+                }],
+                children: Some(ChildrenSchema {
+                    kind: ChildrenKind::Multi,
+                    nodes: vec!["A", "B"],
+                    text: false,
+                }),
+                slots: Vec::new(),
+            }
+        }).collect()
+    }
+}
+
+impl MarkupNode for Tree {
+    fn markup_head(&self) -> MarkupHead {
+        MarkupHead::Node {
+            name: self.name,
+            params: self.params.clone(),
+        }
+    }
+}
+
+impl TemplateRoot for Tree {
+    fn expand_widgets(_curr: &mut Tree, _last: Option<&Tree>, _path: &SimplePathFrame) {}
+
+    fn diff<CTX, D>(curr: &PathFrame<Tree>, last: &PathFrame<Tree>, ctx: &mut Context<CTX, Tree>,
+                    differ: &mut D)
+        where D: Differ<CTX, Tree>
+    {
+        if curr.node().name != last.node().name {
+            return differ.diff_replaced(ctx, curr, last);
+        }
+        if curr.node().params != last.node().params {
+            differ.diff_params_changed(ctx, curr, last);
+        }
+        let (curr_children, last_children) = (&curr.node().children, &last.node().children);
+        for (key, curr_child, last_child) in curr_children.diff(last_children) {
+            match (curr_child, last_child) {
+                (Some((curr_index, curr_child)), Some((last_index, last_child))) => {
+                    Tree::diff(&curr.add_key(key.clone(), curr_index, curr_child),
+                               &last.add_key(key.clone(), last_index, last_child),
+                               ctx,
+                               differ)
+                }
+                (Some((curr_index, curr_child)), None) => {
+                    differ.diff_added(ctx, &curr.add_key(key.clone(), curr_index, curr_child))
+                }
+                (None, Some((last_index, last_child))) => {
+                    differ.diff_removed(ctx, &last.add_key(key.clone(), last_index, last_child))
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+        differ.diff_reordered(ctx, curr, None, curr_children.diff_reordered(last_children));
+    }
+}
+
+fn tree(source: &str) -> Tree {
+    template::from_str(source).unwrap()
+}
+
+fn snapshot_path(name: &str) -> String {
+    format!("{}/tests/snapshots/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn snapshot_tree_and_diff() {
+    let last = tree(r#"A { B@1 / B@2 x=1 / B@3 { B/ } }"#);
+    let curr = tree(r#"A { B@2 x=2 / A@3 / B@4 / }"#);
+
+    assert_snapshot(snapshot_path("tree.txt"), &tree_snapshot(&curr));
+    assert_snapshot(snapshot_path("diff.txt"), &diff_snapshot(&curr, &last));
+}
+
+/// Widget rendering its input as a label.
+#[derive(Debug, Clone)]
+struct Badge;
+
+impl Widget for Badge {
+    type Input = String;
+    type Output = Any;
+
+    fn new() -> Badge {
+        Badge
+    }
+
+    fn render(&self, text: String) -> Option<Any> {
+        Some(markup!(Label { (text) }))
+    }
+}
+
+#[test]
+fn snapshot_nodes_tree_and_diff() {
+    let last: AllNodes = template::from_str(r#"
+        Group label="g" {
+            Frame@"frame" { Label { "a" } }
+            Tip@"tip" { Label { "tip" } }
+            Panel@"panel" label="p" /
+            Label@"badge" { "old" }
+        }
+    "#).unwrap();
+    let curr: AllNodes = markup!(Group label="g" {
+        Tip@"tip" /
+        Frame@"frame" { Button label="b" / }
+        Panel@"panel" label="q" {
+            body: { Label@1 { "body" } }
+            footer: <Badge>("footer".to_string())
+        }
+        Card@"card" {
+            title: Label { "title" }
+            action: Button /
+        }
+        <Badge>("new".to_string())@"badge"
+    });
+
+    assert_snapshot(snapshot_path("nodes_tree.txt"), &tree_snapshot(&curr));
+    assert_snapshot(snapshot_path("nodes_diff.txt"), &diff_snapshot(&curr, &last));
+}
+
+#[test]
+fn snapshot_text_diff() {
+    assert_eq!(text_diff("a\nb\nc\n", "a\nc\nd\n"), " a\n-b\n c\n+d\n");
+    assert_eq!(text_diff("", "a\n"), "+a\n");
+}
+
+#[test]
+fn snapshot_written_and_compared() {
+    let path = env::temp_dir().join(format!("vtree-snapshot-{}.txt", process::id()));
+    let _ = fs::remove_file(&path);
+
+    assert_snapshot(&path, "a\nb\n");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+    assert_snapshot(&path, "a\nb\n");
+
+    let mismatch = panic::catch_unwind(|| assert_snapshot(&path, "a\nc\n")).unwrap_err();
+    let message = mismatch.downcast_ref::<String>().unwrap();
+    assert!(message.ends_with(" a\n-b\n+c\n"), "{}", message);
+    fs::remove_file(&path).unwrap();
+}
+
+fn multi(keys: &[Key]) -> Multi<(), ()> {
+    let mut multi = Multi::new();
+    for key in keys {
+        multi.push(key.clone(), ());
+    }
+    multi
+}
+
+#[test]
+fn property_multi_diff() {
+    check_property(500, |rng| {
+        let last = arbitrary_keys(rng, 8);
+        let curr = mutate_keys(rng, &last);
+        check_multi_diff(&multi(&curr), &multi(&last))
+    });
+}
+
+#[test]
+fn property_tree_diff() {
+    check_property(300, |rng| {
+        let (curr, last) = arbitrary_tree_pair::<Tree>(rng);
+        check_diff(&curr, &last)
+    });
+}
+
+/// Replaces some of the children of the `children` slots in `node` by `Badge` widgets.
+fn add_badges(rng: &mut Rng, node: &mut AllNodes) {
+    let children = match *node {
+        AllNodes::Group(ref mut node) => &mut node.children,
+        AllNodes::Dialog(ref mut node) => &mut node.children,
+        AllNodes::Button(ref mut node) => &mut node.children,
+        _ => return,
+    };
+    for (_, child) in children.iter_mut() {
+        if rng.one_in(4) {
+            let text = ["a", "b"][rng.below(2)].to_string();
+            *child = Any::from(WidgetData::<Badge>(text)).into();
+        } else {
+            add_badges(rng, child);
+        }
+    }
+}
+
+#[test]
+fn property_nodes_diff() {
+    check_property(300, |rng| {
+        let (mut curr, mut last) = arbitrary_tree_pair::<AllNodes>(rng);
+        add_badges(rng, &mut curr);
+        add_badges(rng, &mut last);
+        check_diff(&curr, &last)
+    });
+}
+
+#[test]
+fn checks_reject_invalid() {
+    assert!(check_permutation(&[(0, 1), (1, 0)], 2).is_ok());
+    assert!(check_permutation(&[(0, 1)], 2).is_err());
+    assert!(check_permutation(&[(0, 1), (1, 1)], 2).is_err());
+    assert!(check_permutation(&[(2, 0), (0, 2)], 2).is_err());
+
+    assert!(check_unique_keys(&tree("A { B@1 { A@1 / } B@2 / }")).is_ok());
+    // replaced children keep their position for the reorders
+    let (curr, last) = (tree("A { B@1 / A@2 / }"), tree("A { A@2 / A@1 / }"));
+    assert!(check_diff(&curr, &last).is_ok());
+}
@@ -0,0 +1,109 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+mod common;
+
+use std::time::Duration;
+use common::{path, Group, Panel};
+use common::groups::AllNodes;
+use vtree::diff::{Context, Path, PathEntry, PathFrame};
+use vtree::transition::{AnimatedNode, ManualClock, Transitions};
+use vtree_markup::markup;
+
+fn diff(transitions: &mut Transitions<&ManualClock, AllNodes>, curr: &AllNodes, last: &AllNodes) {
+    let mut ctx = Context::new(());
+    AllNodes::diff(&PathFrame::new(curr), &PathFrame::new(last), &mut ctx, transitions);
+}
+
+#[test]
+fn transition_params() {
+    let clock = ManualClock::new();
+    let mut transitions = Transitions::new(&clock, Duration::from_millis(100));
+
+    let last: AllNodes = markup!(Group x=0.0 /);
+    let curr: AllNodes = markup!(Group x=10.0 /);
+    diff(&mut transitions, &curr, &last);
+    assert_eq!(transitions.values(&path(&[])), Some(vec![0.0]));
+
+    clock.advance(Duration::from_millis(25));
+    let interpolated = transitions.interpolated(&path(&[]), &curr).unwrap();
+    assert_eq!(interpolated.animated_values(), vec![2.5]);
+
+    // retargeting continues from the current value
+    let last = curr;
+    let curr: AllNodes = markup!(Group x=0.0 /);
+    diff(&mut transitions, &curr, &last);
+    assert_eq!(transitions.values(&path(&[])), Some(vec![2.5]));
+
+    clock.advance(Duration::from_millis(50));
+    assert!(transitions.tick());
+    assert_eq!(transitions.values(&path(&[])), Some(vec![1.25]));
+
+    clock.advance(Duration::from_millis(50));
+    assert!(!transitions.tick());
+    assert_eq!(transitions.values(&path(&[])), None);
+}
+
+#[test]
+fn transition_exit_and_move() {
+    let clock = ManualClock::new();
+    let mut transitions = Transitions::new(&clock, Duration::from_millis(100));
+
+    let last: AllNodes = markup!(Group {
+        Group@"a" x=1.0 /
+        Group@"b" x=2.0 /
+        Group@"c" x=3.0 /
+    });
+    let curr: AllNodes = markup!(Group {
+        Group@"c" x=3.0 /
+        Group@"a" x=1.0 /
+    });
+    diff(&mut transitions, &curr, &last);
+
+    clock.advance(Duration::from_millis(25));
+    {
+        let exiting: Vec<_> = transitions.exiting().collect();
+        assert_eq!(exiting.len(), 1);
+        assert_eq!(exiting[0].0.path, path(&["b"]));
+        assert_eq!(exiting[0].0.node.animated_values(), vec![2.0]);
+        assert_eq!(exiting[0].1, 0.25);
+    }
+    let (m, progress) = transitions.moved(&path(&["c"])).unwrap();
+    assert_eq!((m.from, m.to, progress), (1, 0, 0.25));
+    let (m, _) = transitions.moved(&path(&["a"])).unwrap();
+    assert_eq!((m.from, m.to), (0, 1));
+
+    clock.advance(Duration::from_millis(75));
+    assert!(!transitions.tick());
+    assert_eq!(transitions.exiting().count(), 0);
+    assert!(transitions.moved(&path(&["c"])).is_none());
+}
+
+#[test]
+fn transition_move_within_slot() {
+    let clock = ManualClock::new();
+    let mut transitions = Transitions::new(&clock, Duration::from_millis(100));
+
+    let panel = |footer: &[&'static str]| -> AllNodes {
+        markup!(Panel {
+            body: Group@"a" /
+            footer: {
+                for key in footer @(*key) {
+                    Group /
+                }
+            }
+        })
+    };
+    diff(&mut transitions, &panel(&["b", "a"]), &panel(&["a", "b"]));
+
+    let body_a: Path = vec![PathEntry::Empty, PathEntry::SlotKey("body", "a".into())]
+        .into_iter()
+        .collect();
+    assert!(transitions.moved(&body_a).is_none());
+    let footer_a: Path = vec![PathEntry::Empty, PathEntry::SlotKey("footer", "a".into())]
+        .into_iter()
+        .collect();
+    let (m, _) = transitions.moved(&footer_a).unwrap();
+    assert_eq!((m.from, m.to), (0, 1));
+}
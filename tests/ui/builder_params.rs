@@ -0,0 +1,49 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+use vtree_macros::{define_nodes, define_params};
+use vtree_markup::markup;
+use groups::AllNodes;
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+define_params!{
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ImageParams {
+        src: String,
+    }
+}
+
+define_params!{
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct IconParams {
+        #[required]
+        name: String,
+        size: u32,
+    }
+}
+
+define_nodes!{
+    nodes {
+        Image<::ImageParams>,
+        Icon<::IconParams>,
+    }
+    groups {}
+}
+
+fn main() {
+    // fine, the params are set
+    Image::builder().params().set_src("a.png".into()).build().build();
+    Icon::builder().params().set_name("close".into()).build().build();
+    let _: AllNodes = markup!(Icon name="close" size=16 /);
+    // `ImageParams` has no default to fall back to
+    Image::builder().build();
+    let _ = Image::default();
+    // `IconParams` derives `Default`, but can't default its required `name`
+    Icon::builder().build();
+    let _ = Icon::default();
+    Icon::builder().params().set_size(16).build().build();
+    let _: AllNodes = markup!(Icon size=16 /);
+}
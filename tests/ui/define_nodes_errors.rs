@@ -0,0 +1,166 @@
+extern crate vtree_macros;
+
+mod duplicate_node {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            B: mul A,
+            A,
+            A,
+        }
+        groups {}
+    }
+}
+
+mod unknown_node {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            A {
+                header: opt A,
+                body: mul B,
+            },
+        }
+        groups {}
+    }
+}
+
+mod cyclic_group {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            A: mul @Foo,
+            B,
+        }
+        groups {
+            Foo: A @Bar,
+            Bar: B @Foo,
+        }
+    }
+}
+
+mod duplicate_slot {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            A {
+                body: mul A,
+                body: opt A,
+            },
+        }
+        groups {}
+    }
+}
+
+mod relative_params {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            A<params::AParams>,
+        }
+        groups {}
+    }
+}
+
+mod generic_node {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            List<T>,
+        }
+        groups {}
+    }
+}
+
+mod role {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            #[role = "button"]
+            A,
+            #[role = 1]
+            B,
+        }
+        groups {}
+    }
+}
+
+mod reserved_slot {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            A {
+                body: mul A,
+                build: opt A,
+            },
+        }
+        groups {}
+    }
+}
+
+mod setter_slot {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            A {
+                body: mul A,
+                set_body: opt A,
+            },
+        }
+        groups {}
+    }
+}
+
+mod fixpoint_section {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            A: mul @Foo,
+        }
+        groups(fixpoint) {
+            Foo: A @Bar,
+            Bar: @Foo,
+        }
+        nodes {
+            B: mul @Baz,
+        }
+        groups {
+            Baz: B @Qux,
+            Qux: @Baz @Foo,
+        }
+    }
+}
+
+mod group_partial_eq {
+    use vtree_macros::define_nodes;
+
+    define_nodes!{
+        nodes {
+            /// Compares equal to any other `A`.
+            #[derive(PartialEq)]
+            A,
+            /** Not comparable. */
+            B,
+        }
+        groups {
+            /// Only `A`.
+            #[derive(PartialEq)]
+            Comparable: A,
+            /// Both.
+            #[derive(PartialEq)]
+            Both: A B,
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+extern crate vtree_markup;
+
+use vtree_markup::markup;
+
+fn main() {
+    markup!(A {
+        B@1 /
+        B@1.5 /
+    });
+    markup!(A {
+        B@"b" x=1 y= /
+    });
+    markup!(A {
+        for item in items @(item.id) {
+            B /
+            C@(item.label) {
+                D@1 /
+            }
+            if item.done {
+                E@"done" /
+            }
+        }
+    });
+}
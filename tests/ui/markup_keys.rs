@@ -0,0 +1,56 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+use vtree_macros::define_nodes;
+use vtree_markup::markup;
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+define_nodes!{
+    nodes {
+        Label: mul Text,
+        Row: mul @Inline,
+        Card {
+            body: mul @Inline,
+        },
+    }
+    groups {
+        Inline: Label Row,
+    }
+}
+
+fn main() {
+    let items = vec![1u64, 2];
+    // fine, the children are keyed alike
+    let _: groups::AllNodes = markup!(Row {
+        Label@1 { "a" }
+        for item in &items @(*item) {
+            Label { "b" }
+        }
+    });
+    let _: groups::AllNodes = markup!(Row {
+        Label { "a" }
+        Row /
+    });
+    // mixed keys, within `if` too
+    let _: groups::AllNodes = markup!(Row {
+        Label@1 { "a" }
+        Row /
+    });
+    let _: groups::AllNodes = markup!(Row {
+        if items.is_empty() { Label@"empty" { "none" } }
+        Row /
+    });
+    let _: groups::AllNodes = markup!(Card {
+        body: {
+            Label@1 { "a" }
+            Row /
+        }
+    });
+    // keys can't be bools
+    let _: groups::AllNodes = markup!(Row {
+        Label@true { "a" }
+    });
+}
@@ -0,0 +1,53 @@
+extern crate vtree;
+extern crate vtree_macros;
+extern crate vtree_markup;
+
+use vtree_macros::define_nodes;
+use vtree_markup::markup;
+
+#[derive(Debug)]
+pub enum AllEvent {}
+
+define_nodes!{
+    nodes {
+        Label: mul Text,
+        Button,
+        Row: mul @Inline,
+        Card {
+            title: opt Label,
+            body: mul @Inline,
+        },
+    }
+    groups {
+        Inline: Label Row,
+    }
+}
+
+fn main() {
+    // fine, the children are in the groups of their slots
+    let _: groups::AllNodes = markup!(Card {
+        title: Label { "title" }
+        body: {
+            Label { "a" }
+            Row {
+                Row { Label { "b" } }
+            }
+        }
+    });
+    let _: groups::AllNodes = markup!(Card {
+        title: Button /
+        body: {
+            Label { "a" }
+            Button /
+        }
+    });
+    let _: groups::AllNodes = markup!(Row {
+        Label { "a" }
+        Row {
+            Button /
+        }
+    });
+    let _: groups::AllNodes = markup!(Button {
+        Label { "a" }
+    });
+}
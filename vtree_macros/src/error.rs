@@ -0,0 +1,47 @@
+use proc_macro::{Delimiter, Group, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use proc_macro;
+use span::span_at;
+
+/// Position in the macro input, as the length of the input following it. The parsers only see
+/// the rest of the input, whose length is the same in the whole input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pos(pub usize);
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    msg: String,
+    pos: Pos,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(msg: S, pos: Pos) -> Error {
+        Error {
+            msg: msg.into(),
+            pos: pos,
+        }
+    }
+
+    /// Renders the error as `compile_error!` spanned at its position within `input`.
+    pub fn to_compile_error(&self, input: &TokenStream) -> TokenStream {
+        let span = self.span(input);
+
+        let mut msg = Literal::string(&self.msg);
+        msg.set_span(span);
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(span);
+        let mut body = Group::new(Delimiter::Brace, TokenTree::Literal(msg).into());
+        body.set_span(span);
+
+        vec![
+            TokenTree::Ident(proc_macro::Ident::new("compile_error", span)),
+            TokenTree::Punct(bang),
+            TokenTree::Group(body),
+        ].into_iter()
+            .collect()
+    }
+
+    fn span(&self, input: &TokenStream) -> Span {
+        let len = input.to_string().len();
+        span_at(input, len.saturating_sub(self.pos.0))
+    }
+}
@@ -1,239 +1,273 @@
+use syn::Ident;
 use quote::Tokens;
-use parser::{ParsedData, ChildType, Node, Child};
+use parser::{ParsedData, ChildType, NodeNormal, Slot};
+use super::gen_slot_ty;
 
-pub fn gen_builders<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + 'a {
-    pd.normal_nodes().map(move |node| {
-        let name = &node.name;
-        let name_str = node.name.as_ref();
+/// Builder marker type of `slot`, distinguishing the `BuilderSetter` impls of the slots.
+fn slot_marker(node: &NodeNormal, slot: &Slot) -> Tokens {
+    match slot.name {
+        Some(ref name) => {
+            let marker = marker_ident(node, name);
+            quote!{slots::#marker}
+        }
+        None => quote!{::vtree::node::BuilderChild},
+    }
+}
+
+fn marker_ident(node: &NodeNormal, slot_name: &Ident) -> Ident {
+    let mut marker = node.name.to_string();
+    for part in slot_name.as_ref().split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            marker.extend(first.to_uppercase());
+            marker.push_str(chars.as_str());
+        }
+    }
+    Ident::new(marker)
+}
 
+pub fn gen_slot_markers<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + 'a {
+    pd.normal_nodes().flat_map(|node| {
+        node.slots.iter().filter_map(move |slot| {
+            slot.name.as_ref().map(|name| {
+                let marker = marker_ident(node, name);
+                let cfgs = node.cfg_attrs();
+                quote!{
+                    #(#cfgs)*
+                    pub struct #marker;
+                }
+            })
+        })
+    })
+}
+
+/// Generics of the impls of the builder of `node` and the builder type, the builders of nodes
+/// with params carry the type-state of the params.
+fn builder_generics(node: &NodeNormal) -> (Option<Tokens>, Tokens) {
+    let name = &node.name;
+    match node.params_ty {
+        Some(_) => (Some(quote!{<S>}), quote!{#name<S>}),
+        None => (None, quote!{#name}),
+    }
+}
 
-        let maybe_params_field = node.params_ty.as_ref().map(|params_ty| {
+fn gen_slot_builder(node: &NodeNormal, slot: &Slot, pd: &ParsedData) -> Tokens {
+    let (impl_generics, name) = builder_generics(node);
+    let name = &name;
+    let field = slot.field();
+    let set_fn = Ident::new(format!("set_{}", field));
+    let marker = slot_marker(node, slot);
+    let (child_name, child_ty) = gen_slot_ty(slot, pd, &quote!{super::});
+    let children_builder = match slot.ty {
+        ChildType::Single => {
             quote!{
-                params: ::std::option::Option<#params_ty>,
+                ::vtree::child::SingleBuilder
             }
-        });
-
-        let maybe_child_field = node.child.as_ref().map(move |&(ty, ref child)| {
-            let child_name = match child {
-                &Child::Node(ref name) => {
-                    match pd.node_by_name(name) {
-                        Some(&Node::Normal(..)) => quote!{super::#name},
-                        Some(&Node::Text) => quote!{::std::borrow::Cow<'static, str>},
-                        None => unreachable!(),
-                    }
-                }
-                &Child::Group(ref name) => quote!{super::groups::#name},
-            };
-            let ty = match ty {
-                ChildType::Single => {
-                    quote!{
-                        ::vtree::child::Single<#child_name, super::groups::AllNodes>,
-                    }
-                }
-                ChildType::Optional => {
-                    quote!{
-                        ::vtree::child::Option<#child_name, super::groups::AllNodes>,
-                    }
-                }
-                ChildType::Multi => {
-                    quote!{
-                        ::vtree::child::Multi<#child_name, super::groups::AllNodes>,
-                    }
-                }
-            };
+        }
+        ChildType::Optional => {
             quote!{
-                children: ::std::option::Option<#ty>,
+                ::vtree::child::OptionBuilder
             }
-        });
+        }
+        ChildType::Multi => {
+            quote!{
+                ::vtree::child::MultiBuilder
+            }
+        }
+    };
+    let cfgs = &node.cfg_attrs();
+    let already_set = match slot.name {
+        Some(ref slot_name) => format!("Slot `{}` already set", slot_name),
+        None => "Children already set".to_string(),
+    };
+
+    quote!{
+        #(#cfgs)*
+        impl #impl_generics #name {
+            pub fn #set_fn(mut self, children: #child_ty) -> #name {
+                assert!(self.#field.is_none(), #already_set);
+                self.#field = Some(children);
+                self
+            }
+
+            pub fn #field(self)
+                -> #children_builder<#name, #child_name, super::groups::AllNodes, #marker>
+            {
+                #children_builder::new(self)
+            }
+        }
+
+        #(#cfgs)*
+        impl #impl_generics ::vtree::node::BuilderSetter<#marker, #child_ty> for #name {
+            fn builder_set(&mut self, value: #child_ty) {
+                assert!(self.#field.is_none(), #already_set);
+                self.#field = Some(value);
+            }
+        }
+    }
+}
 
+pub fn gen_builders<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + 'a {
+    pd.normal_nodes().map(move |node| {
+        let name = &node.name;
+        let name_str = node.name.as_ref();
+        let cfgs = &node.cfg_attrs();
 
-        let maybe_params_constr = node.params_ty.as_ref().map(|_| {
+        let slot_fields = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            let (_, ty) = gen_slot_ty(slot, pd, &quote!{super::});
             quote!{
-                params: None,
+                #field: ::std::option::Option<#ty>,
             }
         });
 
-        let maybe_child_constr = node.child.as_ref().map(|_| {
+        let slot_constrs = node.slots.iter().map(|slot| {
+            let field = slot.field();
             quote!{
-                children: None,
+                #field: None,
             }
         });
 
-
-        let maybe_params_build_arg = node.params_ty.as_ref().map(|_| {
-            let maybe_comma = node.child.as_ref().map(|_| quote!{,});
-
+        let maybe_params_build_arg = node.params_ty.as_ref().map(|params_ty| {
             quote!{
-                self.params.unwrap_or_default()
-                #maybe_comma
+                <S as ::vtree::node::BuilderParamsState<#params_ty>>::params(self.params),
             }
         });
 
-        let maybe_child_build_arg = node.child.as_ref().map(|&(ty, _)| {
-            match ty {
+        let slot_build_args = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            match slot.ty {
                 ChildType::Single => {
-                    let err = format!("Builder: children not set for `{}`", name_str);
+                    let err = match slot.name {
+                        Some(ref slot_name) => {
+                            format!("Builder: slot `{}` not set for `{}`", slot_name, name_str)
+                        }
+                        None => format!("Builder: children not set for `{}`", name_str),
+                    };
                     quote!{
-                        self.children.expect(#err),
+                        self.#field.expect(#err),
                     }
                 }
                 ChildType::Optional | ChildType::Multi => {
                     quote!{
-                        self.children.unwrap_or_default(),
+                        self.#field.unwrap_or_default(),
                     }
                 }
             }
         });
 
+        let slot_builders = node.slots.iter().map(|slot| gen_slot_builder(node, slot, pd));
 
-        let maybe_params_builder_setter_impl = node.params_ty.as_ref().map(|params_ty| {
-            quote!{
-                impl ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #params_ty> for #name {
-                    fn builder_set(&mut self, value: #params_ty) {
-                        assert!(self.params.is_none(), "Params already set");
-                        self.params = Some(value);
+        let params_ty = match node.params_ty {
+            Some(ref params_ty) => params_ty,
+            None => {
+                return quote!{
+                    #(#cfgs)*
+                    pub struct #name {
+                        #(#slot_fields)*
                     }
-                }
-            }
-        });
 
-        let maybe_child_builder_setter_impl = node.child.as_ref().map(|&(ty, ref child)| {
-            let child_name = match child {
-                &Child::Node(ref name) => {
-                    match pd.node_by_name(name) {
-                        Some(&Node::Normal(..)) => quote!{super::#name},
-                        Some(&Node::Text) => quote!{::std::borrow::Cow<'static, str>},
-                        None => unreachable!(),
-                    }
-                }
-                &Child::Group(ref name) => quote!{super::groups::#name},
-            };
-            let child_ty = match ty {
-                ChildType::Single => {
-                    quote!{
-                        ::vtree::child::Single<#child_name, super::groups::AllNodes>,
-                    }
-                }
-                ChildType::Optional => {
-                    quote!{
-                        ::vtree::child::Option<#child_name, super::groups::AllNodes>,
-                    }
-                }
-                ChildType::Multi => {
-                    quote!{
-                        ::vtree::child::Multi<#child_name, super::groups::AllNodes>,
-                    }
-                }
-            };
-            quote!{
-                impl ::vtree::node::BuilderSetter<::vtree::node::BuilderChild, #child_ty> for #name {
-                    fn builder_set(&mut self, value: #child_ty) {
-                        assert!(self.children.is_none(), "Child already set");
-                        self.children = Some(value);
-                    }
-                }
-            }
-        });
+                    #(#cfgs)*
+                    impl #name {
+                        pub fn new() -> #name {
+                            #name {
+                                #(#slot_constrs)*
+                            }
+                        }
 
-        let maybe_params_fn = node.params_ty.as_ref().map(|params_ty| {
-            quote!{
-                pub fn set_params(mut self, params: #params_ty) -> #name {
-                    assert!(self.params.is_none(), "Params already set");
-                    self.params = Some(params);
-                    self
-                }
+                        pub fn build(self) -> super::#name {
+                            super::#name::new(
+                                #(#slot_build_args)*
+                            )
+                        }
+                    }
 
-                pub fn params(self) -> <#params_ty as ::vtree::node::Params<#name>>::Builder {
-                    ::vtree::node::Params::builder(self)
-                }
+                    #(#slot_builders)*
+                };
             }
-        });
+        };
 
-        let maybe_child_fn = node.child.as_ref().map(|&(ty, ref child)| {
-            let child_name = match child {
-                &Child::Node(ref name) => {
-                    match pd.node_by_name(name) {
-                        Some(&Node::Normal(..)) => quote!{super::#name},
-                        Some(&Node::Text) => quote!{::std::borrow::Cow<'static, str>},
-                        None => unreachable!(),
-                    }
-                }
-                &Child::Group(ref name) => quote!{super::groups::#name},
-            };
-            let child_ty = match ty {
-                ChildType::Single => {
-                    quote!{
-                        ::vtree::child::Single<#child_name, super::groups::AllNodes>,
-                    }
-                }
-                ChildType::Optional => {
-                    quote!{
-                        ::vtree::child::Option<#child_name, super::groups::AllNodes>,
-                    }
-                }
-                ChildType::Multi => {
-                    quote!{
-                        ::vtree::child::Multi<#child_name, super::groups::AllNodes>,
-                    }
-                }
-            };
-            let children_builder = match ty {
-                ChildType::Single => {
-                    quote!{
-                        ::vtree::child::SingleBuilder
-                    }
-                }
-                ChildType::Optional => {
-                    quote!{
-                        ::vtree::child::OptionBuilder
-                    }
-                }
-                ChildType::Multi => {
-                    quote!{
-                        ::vtree::child::MultiBuilder
-                    }
-                }
-            };
+        // the builder moved into the `ParamsSet` state
+        let slot_moves = node.slots.iter().map(|slot| {
+            let field = slot.field();
             quote!{
-                pub fn set_children(mut self, children: #child_ty) -> #name {
-                    assert!(self.children.is_none(), "Children already set");
-                    self.children = Some(children);
-                    self
-                }
-
-                pub fn children(self) -> #children_builder<#name, #child_name, super::groups::AllNodes> {
-                    #children_builder::new(self)
-                }
+                #field: self.#field,
             }
         });
+        let slot_moves = quote!{#(#slot_moves)*};
 
         quote!{
-            pub struct #name {
-                #maybe_params_field
-                #maybe_child_field
+            /// `S` is `ParamsUnset` until the params are set, `build()` then falls back to the
+            /// `DefaultParams` of the params type.
+            #(#cfgs)*
+            pub struct #name<S = ::vtree::node::ParamsUnset> {
+                params: ::std::option::Option<#params_ty>,
+                #(#slot_fields)*
+                state_: ::std::marker::PhantomData<S>,
             }
 
+            #(#cfgs)*
             impl #name {
                 pub fn new() -> #name {
                     #name {
-                        #maybe_params_constr
-                        #maybe_child_constr
+                        params: None,
+                        #(#slot_constrs)*
+                        state_: ::std::marker::PhantomData,
+                    }
+                }
+
+                /// Panics if a `validate` or `finalize` hook rejects the params.
+                pub fn set_params(self, params: #params_ty) -> #name<::vtree::node::ParamsSet> {
+                    match self.try_set_params(params) {
+                        ::std::result::Result::Ok(builder) => builder,
+                        ::std::result::Result::Err(err) => panic!("{}", err),
                     }
                 }
 
-                pub fn build(self) -> super::#name {
+                pub fn try_set_params(self, params: #params_ty)
+                    -> ::std::result::Result<#name<::vtree::node::ParamsSet>, ::vtree::node::ParamsError>
+                {
+                    let params = ::vtree::node::CheckParams::check_params(params)?;
+                    ::std::result::Result::Ok(#name {
+                        params: Some(params),
+                        #slot_moves
+                        state_: ::std::marker::PhantomData,
+                    })
+                }
+
+                pub fn params(self)
+                    -> <#params_ty as ::vtree::node::Params<#name<::vtree::node::ParamsSet>>>::Builder
+                {
+                    ::vtree::node::Params::builder(#name {
+                        params: None,
+                        #slot_moves
+                        state_: ::std::marker::PhantomData,
+                    })
+                }
+            }
+
+            #(#cfgs)*
+            impl<S> #name<S> {
+                pub fn build(self) -> super::#name
+                    where S: ::vtree::node::BuilderParamsState<#params_ty>
+                {
                     super::#name::new(
                         #maybe_params_build_arg
-                        #maybe_child_build_arg
+                        #(#slot_build_args)*
                     )
                 }
+            }
 
-                #maybe_params_fn
-                #maybe_child_fn
+            #(#cfgs)*
+            impl ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #params_ty>
+                for #name<::vtree::node::ParamsSet>
+            {
+                fn builder_set(&mut self, value: #params_ty) {
+                    self.params = Some(value);
+                }
             }
 
-            #maybe_params_builder_setter_impl
-            #maybe_child_builder_setter_impl
+            #(#slot_builders)*
         }
     })
 }
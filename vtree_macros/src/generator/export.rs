@@ -0,0 +1,120 @@
+use syn::{Path, PathSegment};
+use quote::Tokens;
+use parser::{Child, ChildType, Export, GroupDef, GroupOp, NodeNormal, Section, Slot};
+
+fn gen_child(child: &Child) -> Tokens {
+    match *child {
+        Child::Node(ref name) => quote!{#name},
+        Child::Group(ref name) => quote!{@#name},
+    }
+}
+
+fn gen_slot_def(slot: &Slot) -> Tokens {
+    let child = gen_child(&slot.child);
+    match slot.ty {
+        ChildType::Single => quote!{#child},
+        ChildType::Optional => quote!{opt #child},
+        ChildType::Multi => quote!{mul #child},
+    }
+}
+
+/// Prefixes the global `params_ty` with `crate_path`, so it resolves from other crates.
+fn rebase(params_ty: &Path, crate_path: Option<&Path>) -> Path {
+    match crate_path {
+        Some(crate_path) if params_ty.global => {
+            let segments: Vec<PathSegment> = crate_path.segments
+                .iter()
+                .chain(params_ty.segments.iter())
+                .cloned()
+                .collect();
+            Path {
+                global: true,
+                segments: segments,
+            }
+        }
+        _ => params_ty.clone(),
+    }
+}
+
+fn gen_node_def(node: &NodeNormal, crate_path: Option<&Path>) -> Tokens {
+    let attrs = &node.attrs;
+    let name = &node.name;
+    let params_ty = node.params_ty.as_ref().map(|params_ty| {
+        let params_ty = rebase(params_ty, crate_path);
+        quote!{<#params_ty>}
+    });
+    let slots = match node.slots.first() {
+        Some(&Slot { name: None, .. }) => {
+            let slot = gen_slot_def(&node.slots[0]);
+            Some(quote!{: #slot})
+        }
+        Some(_) => {
+            let slots = node.slots.iter().map(|slot| {
+                let name = &slot.name;
+                let slot = gen_slot_def(slot);
+                quote!{#name: #slot}
+            });
+            Some(quote!{{ #(#slots),* }})
+        }
+        None => None,
+    };
+    quote!{
+        #(#attrs)*
+        #name #params_ty #slots
+    }
+}
+
+fn gen_group_def(group: &GroupDef) -> Tokens {
+    let attrs = &group.attrs;
+    let name = &group.name;
+    let children = group.children.iter().map(|&(op, ref child, _)| {
+        let child = gen_child(child);
+        match op {
+            GroupOp::Add => quote!{+#child},
+            GroupOp::Sub => quote!{-#child},
+        }
+    });
+    quote!{
+        #(#attrs)*
+        #name: #(#children)*
+    }
+}
+
+fn gen_section(section: &Section, crate_path: Option<&Path>) -> Tokens {
+    let crate_path = if section.own { crate_path } else { None };
+    let nodes = section.nodes.iter().map(|node| gen_node_def(node, crate_path));
+    let groups = section.groups.iter().map(gen_group_def);
+    let fixpoint = section.fixpoint.map(|_| quote!{(fixpoint)});
+    quote!{
+        nodes {
+            #(#nodes),*
+        }
+        groups #fixpoint {
+            #(#groups),*
+        }
+    }
+}
+
+/// Generates the `macro_rules!` of an `export`, which passes the exported sections followed by
+/// its own input to `$crate::define_nodes!`, so the exporting crate has to re-export
+/// `define_nodes` at its root.
+///
+/// The exported nodes are defined again by each invocation, as the generated impls can't be
+/// added to the exporting crate's types from another crate. They don't convert from or to the
+/// exporting crate's nodes, only their params types are referenced by path.
+pub fn gen_export(export: &Export) -> String {
+    let sections = export.sections
+        .iter()
+        .map(|section| gen_section(section, export.crate_path.as_ref()));
+    let sections = quote!{#(#sections)*};
+    let doc = "Defines the nodes and groups of this crate together with the given sections, \
+               like `define_nodes!`. The nodes are declared anew as types of the invoking \
+               module, distinct from this crate's nodes: widgets and trees built with this \
+               crate's nodes don't work with them. Only the params types are shared.";
+    format!(
+        "#[doc = {:?}] #[macro_export] macro_rules! {} {{ ($($rest:tt)*) => {{ $crate::define_nodes!{{ {} $($rest)* }} }}; }}",
+        doc,
+        export.name,
+        sections
+    )
+}
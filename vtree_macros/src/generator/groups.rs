@@ -1,16 +1,50 @@
-use syn::Ident;
+use syn::{Attribute, Ident};
 use quote::Tokens;
-use parser::{ParsedData, ChildType, Node};
+use parser::{ParsedData, ChildType, Child, Node, Slot};
+use super::gen_slot_ty;
 use std::iter::once;
+use std::collections::HashSet;
 
-pub fn gen_group_def<'a, IT>(group: &'a Ident, nodes: IT) -> Tokens
+/// `PartialEq` for a group whose nodes all derive it, widgets are never equal.
+fn gen_group_partial_eq(group: &Ident, nodes: &[&Node]) -> Option<Tokens> {
+    if !nodes.iter().all(|node| node.derives("PartialEq")) {
+        return None;
+    }
+    let variants = nodes.iter().map(|node| {
+        let cfgs = node.cfg_attrs();
+        let node_name = match **node {
+            Node::Normal(ref node) => node.name.clone(),
+            Node::Text => Ident::new("Text"),
+        };
+        quote!{
+            #(#cfgs)*
+            (&#group::#node_name(ref curr), &#group::#node_name(ref other)) => curr == other,
+        }
+    });
+    Some(quote!{
+        impl ::std::cmp::PartialEq for #group {
+            fn eq(&self, other: &#group) -> bool {
+                match (self, other) {
+                    #(#variants)*
+                    _ => false,
+                }
+            }
+        }
+    })
+}
+
+pub fn gen_group_def<'a, IT>(group: &'a Ident, attrs: &[Attribute], nodes: IT) -> Tokens
     where IT: Iterator<Item = &'a Node>
 {
-    let vars = nodes.map(|node| {
-        match node {
+    let nodes: Vec<_> = nodes.collect();
+    let partial_eq = gen_group_partial_eq(group, &nodes);
+    let vars = nodes.iter().map(|node| {
+        match *node {
             &Node::Normal(ref node) => {
+                let cfgs = node.cfg_attrs();
                 let node = &node.name;
                 quote!{
+                    #(#cfgs)*
                     #node(super::#node),
                 }
             }
@@ -25,10 +59,55 @@ pub fn gen_group_def<'a, IT>(group: &'a Ident, nodes: IT) -> Tokens
 
     quote!{
         #[derive(Debug, Clone)]
+        #(#attrs)*
         pub enum #group {
             #(#vars)*
             Widget(::std::boxed::Box<::vtree::widget::WidgetDataTrait<#group>>),
         }
+
+        #partial_eq
+    }
+}
+
+/// Adds the path entry of a Single or Option child in `slot` to `frame`, `node` being the child
+/// for a `PathFrame`.
+fn gen_add_single(slot: &Slot, frame: Tokens, node: Option<Tokens>) -> Tokens {
+    match slot.name {
+        Some(ref name) => {
+            let name = name.as_ref();
+            match node {
+                Some(node) => quote!{#frame.add_slot(#name, #node)},
+                None => quote!{#frame.add_slot(#name)},
+            }
+        }
+        None => quote!{#frame.add_empty(#node)},
+    }
+}
+
+/// Adds the path entry of the Multi child with `key` in `slot` to `frame`, `index_node` being
+/// the index and child for a `PathFrame`.
+fn gen_add_multi(slot: &Slot, frame: Tokens, index_node: Option<(Tokens, Tokens)>) -> Tokens {
+    let (index, node) = match index_node {
+        Some((index, node)) => (Some(quote!{, #index}), Some(quote!{, #node})),
+        None => (None, None),
+    };
+    match slot.name {
+        Some(ref name) => {
+            let name = name.as_ref();
+            quote!{#frame.add_slot_key(#name, key.clone() #index #node)}
+        }
+        None => quote!{#frame.add_key(key.clone() #index #node)},
+    }
+}
+
+/// `Option` of the slot name as passed to `Differ::diff_reordered`.
+fn gen_slot_name(slot: &Slot) -> Tokens {
+    match slot.name {
+        Some(ref name) => {
+            let name = name.as_ref();
+            quote!{::std::option::Option::Some(#name)}
+        }
+        None => quote!{::std::option::Option::None},
     }
 }
 
@@ -44,95 +123,109 @@ fn gen_all_nodes_impl_expand_widgets(pd: &ParsedData) -> Tokens {
         };
 
         let node_name = &node.name;
+        let cfgs = &node.cfg_attrs();
 
-        let ty = if let Some((ty, _)) = node.child {
-            ty
-        } else {
+        if node.slots.is_empty() {
             return quote!{
+                #(#cfgs)*
                 (&mut AllNodes::#node_name(..), _) => {}
             };
-        };
+        }
 
-        let child_last_some = match ty {
-            ChildType::Single => {
-                quote!{
-                    AllNodes::expand_widgets(
-                        &mut curr_node.children,
-                        Some(&last_node.children),
-                        &path.add_empty(),
-                    );
-                }
-            }
-            ChildType::Optional => {
-                quote!{
-                    if let Some(children) = curr_node.children {
+        let child_last_some = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            match slot.ty {
+                ChildType::Single => {
+                    let path = gen_add_single(slot, quote!{path}, None);
+                    quote!{
                         AllNodes::expand_widgets(
-                            children,
-                            last_node.children,
-                            &path.add_empty(),
+                            &mut curr_node.#field,
+                            Some(&*last_node.#field),
+                            &#path,
                         );
                     }
                 }
-            }
-            ChildType::Multi => {
-                quote!{
-                    for (key, node) in curr_node.children.iter_mut() {
-                        AllNodes::expand_widgets(
-                            node,
-                            last_node.children.get_by_key(key),
-                            &path.add_key(key.clone()),
-                        );
+                ChildType::Optional => {
+                    let path = gen_add_single(slot, quote!{path}, None);
+                    quote!{
+                        if let Some(ref mut child) = *curr_node.#field {
+                            AllNodes::expand_widgets(
+                                child,
+                                last_node.#field.as_ref().map(|n| &**n),
+                                &#path,
+                            );
+                        }
                     }
                 }
-            }
-        };
-
-        let child_last_none = match ty {
-            ChildType::Single => {
-                quote!{
-                    AllNodes::expand_widgets(
-                        &mut curr_node.children,
-                        None,
-                        &path.add_empty(),
-                    );
+                ChildType::Multi => {
+                    let path = gen_add_multi(slot, quote!{path}, None);
+                    quote!{
+                        for (key, node) in curr_node.#field.iter_mut() {
+                            AllNodes::expand_widgets(
+                                node,
+                                last_node.#field.get_by_key(key),
+                                &#path,
+                            );
+                        }
+                    }
                 }
             }
-            ChildType::Optional => {
-                quote!{
-                    if let Some(ref mut children) = curr_node.children {
+        });
+
+        let child_last_none = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            match slot.ty {
+                ChildType::Single => {
+                    let path = gen_add_single(slot, quote!{path}, None);
+                    quote!{
                         AllNodes::expand_widgets(
-                            children,
+                            &mut curr_node.#field,
                             None,
-                            &path.add_empty(),
+                            &#path,
                         );
                     }
                 }
-            }
-            ChildType::Multi => {
-                quote!{
-                    for (key, node) in curr_node.children.iter_mut() {
-                        AllNodes::expand_widgets(
-                            node,
-                            None,
-                            &path.add_key(key.clone()),
-                        );
+                ChildType::Optional => {
+                    let path = gen_add_single(slot, quote!{path}, None);
+                    quote!{
+                        if let Some(ref mut child) = *curr_node.#field {
+                            AllNodes::expand_widgets(
+                                child,
+                                None,
+                                &#path,
+                            );
+                        }
+                    }
+                }
+                ChildType::Multi => {
+                    let path = gen_add_multi(slot, quote!{path}, None);
+                    quote!{
+                        for (key, node) in curr_node.#field.iter_mut() {
+                            AllNodes::expand_widgets(
+                                node,
+                                None,
+                                &#path,
+                            );
+                        }
                     }
                 }
             }
-        };
+        });
 
         quote!{
+            #(#cfgs)*
             (
                 &mut AllNodes::#node_name(ref mut curr_node),
                 Some(&AllNodes::#node_name(ref last_node))
             ) => {
-                #child_last_some
+                #(#child_last_some)*
             }
+            #(#cfgs)*
             (
                 &mut AllNodes::#node_name(ref mut curr_node),
                 _
             ) => {
-                #child_last_none
+                #(#child_last_none)*
             }
         }
     });
@@ -152,10 +245,10 @@ fn gen_all_nodes_impl_expand_widgets(pd: &ParsedData) -> Tokens {
                 };
                 match widget_data.render() {
                     Some(result) => {
-                        ::std::mem::replace(curr, result);
+                        *curr = result;
                     }
                     None => {
-                        ::std::mem::replace(curr, last.unwrap().clone());
+                        *curr = last.unwrap().clone();
                         return;
                     }
                 }
@@ -187,79 +280,91 @@ fn gen_all_nodes_impl_diff(pd: &ParsedData) -> Tokens {
             }
         };
 
-        let maybe_child = node.child.as_ref().map(|&(ty, _)| {
-            match ty {
+        let slots = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            match slot.ty {
                 ChildType::Single => {
+                    let c = gen_add_single(slot, quote!{curr}, Some(quote!{&curr_node.#field}));
+                    let l = gen_add_single(slot, quote!{last}, Some(quote!{&last_node.#field}));
                     quote!{
                         AllNodes::diff(
-                            &curr.add_empty(&curr_node.children),
-                            &last.add_empty(&last_node.children),
+                            &#c,
+                            &#l,
                             ctx,
                             differ,
                         );
                     }
                 }
                 ChildType::Optional => {
+                    let c = gen_add_single(slot, quote!{curr}, Some(quote!{curr_child}));
+                    let l = gen_add_single(slot, quote!{last}, Some(quote!{last_child}));
                     quote!{
-                        match (&curr_node.children, &last_node.children) {
+                        match (&*curr_node.#field, &*last_node.#field) {
                             (&Some(ref curr_child), &Some(ref last_child)) =>
                                 AllNodes::diff(
-                                    &curr.add_empty(curr_child),
-                                    &last.add_empty(last_child),
+                                    &#c,
+                                    &#l,
                                     ctx,
                                     differ,
                                 ),
-                            (&Some(ref curr_child), None) => {
-                                let c = curr.add_empty(curr_child);
+                            (&Some(ref curr_child), &None) => {
+                                let c = #c;
                                 differ.on_enter_curr(ctx, &c);
                                 differ.diff_added(ctx, &c);
                                 differ.on_exit_curr(ctx, &c);
                             }
-                            (None, &Some(ref last_child)) => {
-                                let l = last.add_empty(last_child);
+                            (&None, &Some(ref last_child)) => {
+                                let l = #l;
                                 differ.on_enter_last(ctx, &l);
                                 differ.diff_removed(ctx, &l);
                                 differ.on_exit_last(ctx, &l);
                             }
-                            (None, None) => {}
+                            (&None, &None) => {}
                         }
                     }
                 }
                 ChildType::Multi => {
+                    let c = gen_add_multi(slot, quote!{curr},
+                                          Some((quote!{curr_index}, quote!{curr_child})));
+                    let l = gen_add_multi(slot, quote!{last},
+                                          Some((quote!{last_index}, quote!{last_child})));
+                    let slot_name = gen_slot_name(slot);
                     quote!{
-                        let field_diff = curr_node.children.diff(&last_node.children);
+                        let field_diff = curr_node.#field.diff(&last_node.#field);
                         for (key, curr_child, last_child) in field_diff {
                             match (curr_child, last_child) {
                                 (Some((curr_index, curr_child)), Some((last_index, last_child))) =>
                                     AllNodes::diff(
-                                        &curr.add_key(key.clone(), curr_index, curr_child),
-                                        &last.add_key(key.clone(), last_index, last_child),
+                                        &#c,
+                                        &#l,
                                         ctx,
                                         differ
                                     ),
-                                (Some((curr_index, curr_child)), None) => {}
-                                    let c = curr.add_key(key.clone(), curr_index, curr_child);
+                                (Some((curr_index, curr_child)), None) => {
+                                    let c = #c;
                                     differ.on_enter_curr(ctx, &c);
                                     differ.diff_added(ctx, &c);
                                     differ.on_exit_curr(ctx, &c);
                                 }
-                                (None, Some((last_index, last_child))) =>
-                                    let l = last.add_key(key.clone(), last_index, last_child);
+                                (None, Some((last_index, last_child))) => {
+                                    let l = #l;
                                     differ.on_enter_last(ctx, &l);
-                                    differ.diff_removed(ctx, &l),
+                                    differ.diff_removed(ctx, &l);
                                     differ.on_exit_last(ctx, &l);
+                                }
                                 (None, None) => unreachable!(),
                             }
                         }
 
-                        let reordered = curr_node.children.diff_reordered(&last_node.children);
-                        differ.diff_reordered(ctx, curr, reordered);
+                        let reordered = curr_node.#field.diff_reordered(&last_node.#field);
+                        differ.diff_reordered(ctx, curr, #slot_name, reordered);
                     }
                 }
             }
         });
 
         let node_name = &node.name;
+        let cfgs = &node.cfg_attrs();
 
         let maybe_params_cmp = node.params_ty.as_ref().map(|_| quote!{
             if curr_node.params != last_node.params {
@@ -269,15 +374,17 @@ fn gen_all_nodes_impl_diff(pd: &ParsedData) -> Tokens {
 
         quote!{
             // equal types
+            #(#cfgs)*
             (
                 &AllNodes::#node_name(ref curr_node),
                 &AllNodes::#node_name(ref last_node)
             ) => {
                 #maybe_params_cmp
-                #maybe_child
+                #(#slots)*
             }
 
             // replaced
+            #(#cfgs)*
             (
                 &AllNodes::#node_name(..),
                 _
@@ -296,56 +403,59 @@ fn gen_all_nodes_impl_diff(pd: &ParsedData) -> Tokens {
         )
             where D: ::vtree::diff::Differ<CTX, AllNodes>
         {
-            diff.on_enter_curr(ctx, curr);
-            diff.on_enter_last(ctx, last);
+            differ.on_enter_curr(ctx, curr);
+            differ.on_enter_last(ctx, last);
             match (curr.node(), last.node()) {
                 (&AllNodes::Widget(_), _) => panic!("curr isn't allowed to be a AllNodes::Widget in diff"),
                 (_, &AllNodes::Widget(_)) => panic!("last isn't allowed to be a AllNodes::Widget in diff"),
                 #(#variants)*
             }
-            diff.on_exit_curr(ctx, curr);
-            diff.on_exit_last(ctx, last);
+            differ.on_exit_curr(ctx, curr);
+            differ.on_exit_last(ctx, last);
         }
     }
 }
 
 fn gen_all_nodes_impl_visit_variants<'a>(pd: &'a ParsedData, is_enter: bool) -> impl Iterator<Item = Tokens> + 'a  {
     let name_visit = Ident::from(if is_enter {"visit_enter"} else {"visit_exit"});
-    pd.normal_nodes().filter_map(move |node| {
-        let ty = if let Some((ty, _)) = node.child {
-            ty
-        } else {
-            return None;
-        };
-        let child = match ty {
-            ChildType::Single => {
-                quote!{
-                    AllNodes::#name_visit(&curr.add_empty(&curr_node.children), f);
+    pd.normal_nodes().filter(|node| !node.slots.is_empty()).map(move |node| {
+        let slots = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            match slot.ty {
+                ChildType::Single => {
+                    let c = gen_add_single(slot, quote!{curr}, Some(quote!{&curr_node.#field}));
+                    quote!{
+                        AllNodes::#name_visit(&#c, f);
+                    }
                 }
-            }
-            ChildType::Optional => {
-                quote!{
-                    if let Some(ref node) = &curr_node.children {
-                        AllNodes::#name_visit(&curr.add_empty(node), f);
+                ChildType::Optional => {
+                    let c = gen_add_single(slot, quote!{curr}, Some(quote!{node}));
+                    quote!{
+                        if let Some(ref node) = *curr_node.#field {
+                            AllNodes::#name_visit(&#c, f);
+                        }
                     }
                 }
-            }
-            ChildType::Multi => {
-                quote!{
-                    let it = curr_node.children.iter().enumerate();
-                    for (index, (key, node)) in it {
-                        AllNodes::#name_visit(&curr.add_key(key.clone(), index, node), f);
+                ChildType::Multi => {
+                    let c = gen_add_multi(slot, quote!{curr}, Some((quote!{index}, quote!{node})));
+                    quote!{
+                        let it = curr_node.#field.iter().enumerate();
+                        for (index, (key, node)) in it {
+                            AllNodes::#name_visit(&#c, f);
+                        }
                     }
                 }
             }
-        };
+        });
 
         let node_name = &node.name;
-        Some(quote!{
+        let cfgs = node.cfg_attrs();
+        quote!{
+            #(#cfgs)*
             &AllNodes::#node_name(ref curr_node) => {
-                #child
+                #(#slots)*
             }
-        })
+        }
     })
 }
 
@@ -445,6 +555,237 @@ pub fn gen_all_nodes_impl(pd: &ParsedData) -> Tokens {
     }
 }
 
+pub fn gen_all_nodes_node_children_impl(pd: &ParsedData) -> Tokens {
+    let variants = pd.normal_nodes().filter(|node| !node.slots.is_empty()).map(|node| {
+        let slots = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            match slot.ty {
+                ChildType::Single => {
+                    let entry = match slot.name {
+                        Some(ref name) => {
+                            let name = name.as_ref();
+                            quote!{::vtree::diff::PathIndexEntry::Slot(#name)}
+                        }
+                        None => quote!{::vtree::diff::PathIndexEntry::Empty},
+                    };
+                    quote!{
+                        f(#entry, &curr_node.#field);
+                    }
+                }
+                ChildType::Optional => {
+                    let entry = match slot.name {
+                        Some(ref name) => {
+                            let name = name.as_ref();
+                            quote!{::vtree::diff::PathIndexEntry::Slot(#name)}
+                        }
+                        None => quote!{::vtree::diff::PathIndexEntry::Empty},
+                    };
+                    quote!{
+                        if let Some(ref node) = *curr_node.#field {
+                            f(#entry, node);
+                        }
+                    }
+                }
+                ChildType::Multi => {
+                    let entry = match slot.name {
+                        Some(ref name) => {
+                            let name = name.as_ref();
+                            quote!{::vtree::diff::PathIndexEntry::SlotKey(#name, key.clone(), index)}
+                        }
+                        None => quote!{::vtree::diff::PathIndexEntry::Key(key.clone(), index)},
+                    };
+                    quote!{
+                        let it = curr_node.#field.iter().enumerate();
+                        for (index, (key, node)) in it {
+                            f(#entry, node);
+                        }
+                    }
+                }
+            }
+        });
+
+        let node_name = &node.name;
+        let cfgs = node.cfg_attrs();
+        quote!{
+            #(#cfgs)*
+            &AllNodes::#node_name(ref curr_node) => {
+                #(#slots)*
+            }
+        }
+    });
+
+    quote!{
+        impl ::vtree::node::NodeChildren for AllNodes {
+            fn for_each_child<'a, F>(&'a self, mut f: F)
+                where F: ::std::ops::FnMut(::vtree::diff::PathIndexEntry, &'a AllNodes)
+            {
+                match self {
+                    #(#variants)*
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+pub fn gen_all_nodes_layout_node_impl(pd: &ParsedData) -> Tokens {
+    let variants = pd.normal_nodes()
+        .filter(|node| node.params_ty.is_some())
+        .map(|node| {
+            let node_name = &node.name;
+            let cfgs = node.cfg_attrs();
+            quote!{
+                #(#cfgs)*
+                &AllNodes::#node_name(ref node) =>
+                    ::vtree::layout::LayoutParams::layout_style(&node.params),
+            }
+        });
+
+    quote!{
+        impl ::vtree::layout::LayoutNode for AllNodes {
+            fn layout_style(&self) -> ::vtree::layout::Style {
+                match self {
+                    #(#variants)*
+                    _ => ::std::default::Default::default(),
+                }
+            }
+        }
+    }
+}
+
+pub fn gen_all_nodes_focus_node_impl(pd: &ParsedData) -> Tokens {
+    let variants = pd.normal_nodes()
+        .filter(|node| node.params_ty.is_some())
+        .map(|node| {
+            let node_name = &node.name;
+            let cfgs = node.cfg_attrs();
+            quote!{
+                #(#cfgs)*
+                &AllNodes::#node_name(ref node) =>
+                    ::vtree::focus::FocusParams::focusable(&node.params),
+            }
+        });
+
+    quote!{
+        impl ::vtree::focus::FocusNode for AllNodes {
+            fn focusable(&self) -> bool {
+                match self {
+                    #(#variants)*
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+pub fn gen_all_nodes_accessible_node_impl(pd: &ParsedData) -> Tokens {
+    let role_variants = pd.nodes().filter_map(|node| {
+        match node {
+            &Node::Normal(ref node) => {
+                node.role().map(|role| {
+                    let node_name = &node.name;
+                    let cfgs = node.cfg_attrs();
+                    quote!{
+                        #(#cfgs)*
+                        &AllNodes::#node_name(..) => ::std::option::Option::Some(#role),
+                    }
+                })
+            }
+            &Node::Text => {
+                Some(quote!{
+                    &AllNodes::Text(..) => ::std::option::Option::Some("text"),
+                })
+            }
+        }
+    });
+
+    let props_variants = pd.nodes().filter_map(|node| {
+        match node {
+            &Node::Normal(ref node) => {
+                node.params_ty.as_ref().map(|_| {
+                    let node_name = &node.name;
+                    let cfgs = node.cfg_attrs();
+                    quote!{
+                        #(#cfgs)*
+                        &AllNodes::#node_name(ref node) =>
+                            ::vtree::a11y::AccessibleParams::a11y_props(&node.params),
+                    }
+                })
+            }
+            &Node::Text => {
+                Some(quote!{
+                    &AllNodes::Text(ref text) => ::vtree::a11y::A11yProps {
+                        name: ::std::option::Option::Some(text.to_string()),
+                        ..::std::default::Default::default()
+                    },
+                })
+            }
+        }
+    });
+
+    quote!{
+        impl ::vtree::a11y::AccessibleNode for AllNodes {
+            fn a11y_role(&self) -> ::std::option::Option<&'static str> {
+                match self {
+                    #(#role_variants)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn a11y_props(&self) -> ::vtree::a11y::A11yProps {
+                match self {
+                    #(#props_variants)*
+                    _ => ::std::default::Default::default(),
+                }
+            }
+        }
+    }
+}
+
+pub fn gen_all_nodes_animated_node_impl(pd: &ParsedData) -> Tokens {
+    let nodes_with_params: Vec<_> = pd.normal_nodes()
+        .filter(|node| node.params_ty.is_some())
+        .collect();
+
+    let values_variants = nodes_with_params.iter().map(|node| {
+        let node_name = &node.name;
+        let cfgs = node.cfg_attrs();
+        quote!{
+            #(#cfgs)*
+            &AllNodes::#node_name(ref node) =>
+                ::vtree::transition::AnimatedParams::animated_values(&node.params),
+        }
+    });
+
+    let set_values_variants = nodes_with_params.iter().map(|node| {
+        let node_name = &node.name;
+        let cfgs = node.cfg_attrs();
+        quote!{
+            #(#cfgs)*
+            &mut AllNodes::#node_name(ref mut node) =>
+                ::vtree::transition::AnimatedParams::set_animated_values(&mut node.params, values),
+        }
+    });
+
+    quote!{
+        impl ::vtree::transition::AnimatedNode for AllNodes {
+            fn animated_values(&self) -> ::std::vec::Vec<f64> {
+                match self {
+                    #(#values_variants)*
+                    _ => ::std::vec::Vec::new(),
+                }
+            }
+
+            fn set_animated_values(&mut self, values: &[f64]) {
+                match self {
+                    #(#set_values_variants)*
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
 pub fn gen_group_from_node_impls<'a, IT>(group: &'a Ident,
                                     nodes: IT)
                                     -> impl Iterator<Item = Tokens> + 'a
@@ -463,7 +804,9 @@ pub fn gen_group_from_node_impls<'a, IT>(group: &'a Ident,
             match node {
                 &Node::Normal(ref node) => {
                     let node_name = &node.name;
+                    let cfgs = node.cfg_attrs();
                     quote!{
+                        #(#cfgs)*
                         impl ::std::convert::From<super::#node_name> for #group {
                             fn from(node: super::#node_name) -> #group {
                                 #group::#node_name(node)
@@ -503,7 +846,9 @@ pub fn gen_all_nodes_from_group_impls<'a>(pd: &'a ParsedData) -> impl Iterator<I
             match node {
                 &Node::Normal(ref node) => {
                     let node_name = &node.name;
+                    let cfgs = node.cfg_attrs();
                     quote!{
+                        #(#cfgs)*
                         #group_name::#node_name(node) => AllNodes::#node_name(node),
                     }
                 }
@@ -521,10 +866,61 @@ pub fn gen_all_nodes_from_group_impls<'a>(pd: &'a ParsedData) -> impl Iterator<I
                 fn from(group: #group_name) -> AllNodes {
                     match group {
                         #(#variants)*
-                        #group_name::Widget(_) => unimplemented!(),
+                        #group_name::Widget(widget_data) => {
+                            AllNodes::Widget(::std::boxed::Box::new(
+                                ::vtree::widget::IntoWidgetData(widget_data),
+                            ))
+                        }
                     }
                 }
             }
         }
     })
 }
+
+/// Schema for the nesting checks of `markup!`: `ChildrenGroup` for every node with a `children`
+/// slot and `ChildOf` for every node allowed in one of its slots.
+pub fn gen_children_schema_impls<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + 'a {
+    pd.normal_nodes().map(move |node| {
+        let name = &node.name;
+        let cfgs = node.cfg_attrs();
+        let children_group = node.slots.iter().find(|slot| slot.field() == "children").map(|slot| {
+            let (group, _) = gen_slot_ty(slot, pd, &quote!{super::});
+            let cfgs = &cfgs;
+            quote!{
+                #(#cfgs)*
+                impl ::vtree::node::ChildrenGroup for super::#name {
+                    type Group = #group;
+                }
+            }
+        });
+        // slots accepting the same group share their impls
+        let mut seen = HashSet::new();
+        let child_of_impls = node.slots.iter().filter(|slot| seen.insert(&slot.child)).flat_map(|slot| {
+            let (group, _) = gen_slot_ty(slot, pd, &quote!{super::});
+            let children: Vec<&Node> = match slot.child {
+                Child::Node(ref child) => pd.node_by_name(child).into_iter().collect(),
+                Child::Group(ref group) => {
+                    pd.groups_nodes()
+                        .find(|&(name, _)| name == group)
+                        .map(|(_, nodes)| nodes.collect())
+                        .unwrap_or_default()
+                }
+            };
+            let cfgs = &cfgs;
+            children.into_iter().filter_map(Node::normal).map(move |child| {
+                let child_cfgs = child.cfg_attrs();
+                let child = &child.name;
+                quote!{
+                    #(#cfgs)*
+                    #(#child_cfgs)*
+                    impl ::vtree::node::ChildOf<super::#name, #group> for super::#child {}
+                }
+            }).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+        quote!{
+            #children_group
+            #(#child_of_impls)*
+        }
+    })
+}
@@ -1,39 +1,109 @@
 mod builders;
+mod export;
 mod groups;
 mod nodes;
+mod template;
 
 use self::builders::*;
+use self::export::*;
 use self::groups::*;
 use self::nodes::*;
+use self::template::*;
 
 use syn::Ident;
-use parser::ParsedData;
+use quote::Tokens;
+use parser::{ParsedData, ChildType, Node, Child, Slot};
 use std::iter::once;
 
+/// Child node type and field type of `slot`, `prefix` being the path from the using module to
+/// the node definitions.
+fn gen_slot_ty(slot: &Slot, pd: &ParsedData, prefix: &Tokens) -> (Tokens, Tokens) {
+    let child_name = match slot.child {
+        Child::Node(ref name) => {
+            match pd.node_by_name(name) {
+                Some(&Node::Normal(..)) => quote!{#prefix #name},
+                Some(&Node::Text) => quote!{::std::borrow::Cow<'static, str>},
+                None => unreachable!(),
+            }
+        }
+        Child::Group(ref name) => quote!{#prefix groups::#name},
+    };
+    let ty = match slot.ty {
+        ChildType::Single => {
+            quote!{
+                ::vtree::child::Single<#child_name, #prefix groups::AllNodes>
+            }
+        }
+        ChildType::Optional => {
+            quote!{
+                ::vtree::child::Option<#child_name, #prefix groups::AllNodes>
+            }
+        }
+        ChildType::Multi => {
+            quote!{
+                ::vtree::child::Multi<#child_name, #prefix groups::AllNodes>
+            }
+        }
+    };
+    (child_name, ty)
+}
+
 pub fn generate_defs(pd: ParsedData) -> String {
     let all_nodes_ident = Ident::new("AllNodes");
     let node_defs = gen_node_defs(&pd);
     let group_defs = pd.groups_nodes()
-        .map(|(name, nodes)| gen_group_def(name, nodes))
-        .chain(once(gen_group_def(&all_nodes_ident, pd.nodes())));
+        .map(|(name, nodes)| gen_group_def(name, pd.group_attrs(name), nodes))
+        .chain(once(gen_group_def(&all_nodes_ident, &[], pd.nodes())));
     let all_nodes_impl = gen_all_nodes_impl(&pd);
+    let all_nodes_node_children_impl = gen_all_nodes_node_children_impl(&pd);
+    let all_nodes_layout_node_impl = gen_all_nodes_layout_node_impl(&pd);
+    let all_nodes_focus_node_impl = gen_all_nodes_focus_node_impl(&pd);
+    let all_nodes_accessible_node_impl = gen_all_nodes_accessible_node_impl(&pd);
+    let all_nodes_animated_node_impl = gen_all_nodes_animated_node_impl(&pd);
     let group_from_node_impls = pd.groups_nodes()
         .flat_map(|(name, nodes)| gen_group_from_node_impls(name, nodes))
         .chain(gen_group_from_node_impls(&all_nodes_ident, pd.nodes()));
     let all_nodes_from_group_impls = gen_all_nodes_from_group_impls(&pd);
+    let children_schema_impls = gen_children_schema_impls(&pd);
+    let all_nodes_template_root_impl = gen_all_nodes_template_root_impl();
+    let all_nodes_markup_node_impl = gen_all_nodes_markup_node_impl(&pd);
+    let all_nodes_template_schema_impl = gen_all_nodes_template_schema_impl(&pd);
+    let node_from_template_impls = gen_node_from_template_impls(&pd);
+    let group_from_template_impls = pd.groups_nodes()
+        .map(|(name, nodes)| gen_group_from_template_impl(name, nodes, &pd))
+        .chain(once(gen_group_from_template_impl(&all_nodes_ident, pd.nodes(), &pd)));
     let builders = gen_builders(&pd);
+    let slot_markers = gen_slot_markers(&pd);
     let defs = quote!{
         #(#node_defs)*
         pub mod groups {
             #(#group_defs)*
             #all_nodes_impl
+            #all_nodes_node_children_impl
+            #all_nodes_layout_node_impl
+            #all_nodes_focus_node_impl
+            #all_nodes_accessible_node_impl
+            #all_nodes_animated_node_impl
             #(#group_from_node_impls)*
             #(#all_nodes_from_group_impls)*
+            #(#children_schema_impls)*
+            #(#node_from_template_impls)*
+            #(#group_from_template_impls)*
+            #all_nodes_template_root_impl
+            #all_nodes_markup_node_impl
+            #all_nodes_template_schema_impl
         }
         pub mod builders {
+            pub mod slots {
+                #(#slot_markers)*
+            }
             #(#builders)*
         }
     };
     println!("{}", defs);
-    defs.into_string()
+    let mut defs = defs.into_string();
+    if let Some(ref export) = pd.export {
+        defs.push_str(&gen_export(export));
+    }
+    defs
 }
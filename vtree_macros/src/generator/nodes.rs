@@ -1,13 +1,14 @@
 use quote::Tokens;
-use parser::{ParsedData, ChildType, Node, NodeNormal, Child};
+use parser::{ParsedData, ChildType, NodeNormal};
+use super::gen_slot_ty;
 
 fn gen_node_def_impl(node: &NodeNormal, pd: &ParsedData) -> Tokens {
     let name = &node.name;
+    let cfgs = node.cfg_attrs();
 
     let maybe_params_arg = node.params_ty.as_ref().map(|ty| {
-        let maybe_comma = node.child.as_ref().map(|_| quote!{,});
         quote!{
-            params: #ty #maybe_comma
+            params: #ty,
         }
     });
 
@@ -17,51 +18,37 @@ fn gen_node_def_impl(node: &NodeNormal, pd: &ParsedData) -> Tokens {
         }
     });
 
-    let maybe_child_arg = node.child.as_ref().map(|&(ty, ref name)| {
-        let name = match name {
-            &Child::Node(ref name) => {
-                match pd.node_by_name(name) {
-                    Some(&Node::Normal(..)) => quote!{#name},
-                    Some(&Node::Text) => quote!{::std::borrow::Cow<'static, str>},
-                    None => unreachable!(),
-                }
-            }
-            &Child::Group(ref name) => quote!{groups::#name},
-        };
-        let ty = match ty {
-            ChildType::Single => {
-                quote!{
-                    ::vtree::child::Single<#name, groups::AllNodes>,
-                }
-            }
-            ChildType::Optional => {
-                quote!{
-                    ::vtree::child::Option<#name, groups::AllNodes>,
-                }
-            }
-            ChildType::Multi => {
-                quote!{
-                    ::vtree::child::Multi<#name, groups::AllNodes>,
-                }
-            }
-        };
+    let slot_args = node.slots.iter().map(|slot| {
+        let field = slot.field();
+        let (_, ty) = gen_slot_ty(slot, pd, &quote!{});
+        quote!{
+            #field: #ty,
+        }
+    });
+
+    let slot_constrs = node.slots.iter().map(|slot| {
+        let field = slot.field();
         quote!{
-            children: #ty
+            #field: #field,
         }
     });
 
-    let maybe_child_constr = node.child.as_ref().map(|_| {
+    // the builders check the params, `new` takes them as they are
+    let maybe_new_doc = node.params_ty.as_ref().map(|_| {
         quote!{
-            children: children,
+            /// Takes the params as they are, without running their `validate` and `finalize`
+            /// hooks like the builder does.
         }
     });
 
     quote!{
+        #(#cfgs)*
         impl #name {
-            pub fn new(#maybe_params_arg #maybe_child_arg) -> #name {
+            #maybe_new_doc
+            pub fn new(#maybe_params_arg #(#slot_args)*) -> #name {
                 #name {
                     #maybe_params_constr
-                    #maybe_child_constr
+                    #(#slot_constrs)*
                 }
             }
 
@@ -72,36 +59,59 @@ fn gen_node_def_impl(node: &NodeNormal, pd: &ParsedData) -> Tokens {
     }
 }
 
-pub fn gen_node_defs<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + 'a {
-    pd.normal_nodes().map(move |node| {
-        let maybe_child = node.child.as_ref().map(|&(ty, ref name)| {
-            let name = match name {
-                &Child::Node(ref name) => {
-                    match pd.node_by_name(name) {
-                        Some(&Node::Normal(..)) => quote!{#name},
-                        Some(&Node::Text) => quote!{::std::borrow::Cow<'static, str>},
-                        None => unreachable!(),
-                    }
-                }
-                &Child::Group(ref name) => quote!{groups::#name},
-            };
-            match ty {
-                ChildType::Single => {
-                    quote!{
-                        pub children: ::vtree::child::Single<#name, groups::AllNodes>,
-                    }
-                }
-                ChildType::Optional => {
-                    quote!{
-                        pub children: ::vtree::child::Option<#name, groups::AllNodes>,
+/// `Default` for nodes without Single slots, building them without children. Nodes with params
+/// only implement it if their params implement `DefaultParams`, bounded through a higher-ranked
+/// where clause as a plain one on the concrete params type would fail to compile otherwise.
+fn gen_node_default_impl(node: &NodeNormal) -> Option<Tokens> {
+    let has_single = node.slots.iter().any(|slot| match slot.ty {
+        ChildType::Single => true,
+        ChildType::Optional | ChildType::Multi => false,
+    });
+    if has_single || node.derives("Default") {
+        return None;
+    }
+
+    let name = &node.name;
+    let cfgs = node.cfg_attrs();
+    let body = match node.params_ty {
+        Some(ref params_ty) => {
+            quote!{
+                impl ::std::default::Default for #name
+                    where for<'a> #params_ty: ::vtree::node::DefaultParams +
+                                              ::vtree::node::CheckParams
+                {
+                    fn default() -> #name {
+                        #name::builder().build()
                     }
                 }
-                ChildType::Multi => {
-                    quote!{
-                        pub children: ::vtree::child::Multi<#name, groups::AllNodes>,
+            }
+        }
+        None => {
+            let args = node.slots.iter().map(|_| quote!{::std::default::Default::default()});
+            quote!{
+                impl ::std::default::Default for #name {
+                    fn default() -> #name {
+                        #name::new(#(#args),*)
                     }
                 }
             }
+        }
+    };
+
+    Some(quote!{
+        #(#cfgs)*
+        #body
+    })
+}
+
+pub fn gen_node_defs<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + 'a {
+    pd.normal_nodes().map(move |node| {
+        let slots = node.slots.iter().map(|slot| {
+            let field = slot.field();
+            let (_, ty) = gen_slot_ty(slot, pd, &quote!{});
+            quote!{
+                pub #field: #ty,
+            }
         });
 
         let maybe_params = node.params_ty.as_ref().map(|params| {
@@ -111,15 +121,19 @@ pub fn gen_node_defs<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + '
         });
 
         let name = &node.name;
+        let attrs = node.forwarded_attrs();
         let node_impl = gen_node_def_impl(node, pd);
+        let node_default_impl = gen_node_default_impl(node);
         quote!{
             #[derive(Debug, Clone)]
+            #(#attrs)*
             pub struct #name {
-                #maybe_child
+                #(#slots)*
                 #maybe_params
             }
 
             #node_impl
+            #node_default_impl
         }
     })
 }
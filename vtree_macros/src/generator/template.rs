@@ -0,0 +1,379 @@
+use syn::Ident;
+use quote::Tokens;
+use parser::{ParsedData, ChildType, Child, Node, NodeNormal, Slot};
+
+/// Constructs the field of `slot` from the template node's children, or for named slots from
+/// the children of their `name: { .. }` slot.
+fn gen_slot_from_template(node: &NodeNormal, slot: &Slot) -> Tokens {
+    let name_str = node.name.as_ref();
+    let (children, keyed_children, within) = match slot.name {
+        Some(ref slot_name) => {
+            let slot_str = slot_name.as_ref();
+            (quote!{node.slot_children(#slot_str)},
+             quote!{node.keyed_slot_children(#slot_str)},
+             format!(" in slot `{}`", slot_str))
+        }
+        None => (quote!{node.children()}, quote!{node.keyed_children()}, String::new()),
+    };
+    match slot.ty {
+        ChildType::Single => {
+            let err = format!("`{}` needs exactly one child{}", name_str, within);
+            quote!{{
+                let children = #children;
+                if children.len() != 1 {
+                    return Err(node.error(#err));
+                }
+                ::vtree::child::Single::new(
+                    ::vtree::template::FromTemplate::from_template(&children[0])?
+                )
+            }}
+        }
+        ChildType::Optional => {
+            let err = format!("`{}` takes at most one child{}", name_str, within);
+            quote!{{
+                let children = #children;
+                if children.len() > 1 {
+                    return Err(children[1].error(#err));
+                }
+                ::vtree::child::Option::new(match children.first() {
+                    Some(child) => Some(::vtree::template::FromTemplate::from_template(child)?),
+                    None => None,
+                })
+            }}
+        }
+        ChildType::Multi => {
+            let err = match slot.name {
+                Some(ref slot_name) => {
+                    format!("duplicate key in the slot `{}` of `{}`", slot_name, name_str)
+                }
+                None => format!("duplicate key in the children of `{}`", name_str),
+            };
+            quote!{{
+                let mut children = ::vtree::child::Multi::new();
+                for (key, child) in #keyed_children {
+                    // reported at the child repeating the key, `push` would panic
+                    if children.get_by_key(&key).is_some() {
+                        return Err(child.error(#err));
+                    }
+                    children.push(key, ::vtree::template::FromTemplate::from_template(child)?);
+                }
+                children
+            }}
+        }
+    }
+}
+
+fn gen_node_from_template_impl(node: &NodeNormal) -> Tokens {
+    let name = &node.name;
+    let name_str = name.as_ref();
+    let cfgs = node.cfg_attrs();
+
+    let params = match node.params_ty {
+        Some(ref params_ty) => {
+            Some(quote!{
+                let params =
+                    <#params_ty as ::vtree::template::TemplateParams>::from_template_params(node)?;
+                let params = ::vtree::node::CheckParams::check_params(params)
+                    .map_err(|err| node.error(err.to_string()))?;
+            })
+        }
+        None => {
+            let err = format!("`{}` has no params", name_str);
+            Some(quote!{
+                if let Some(param) = node.params().first() {
+                    return Err(::vtree::template::TemplateError::new(#err, param.position));
+                }
+            })
+        }
+    };
+    let params_arg = node.params_ty.as_ref().map(|_| quote!{params});
+
+    let slot_names: Vec<&str> = node.slots
+        .iter()
+        .filter_map(|slot| slot.name.as_ref().map(|name| name.as_ref()))
+        .collect();
+    let no_children = if node.slots.iter().any(|slot| slot.name.is_none()) {
+        None
+    } else {
+        let err = if slot_names.is_empty() {
+            format!("`{}` can't have children", name_str)
+        } else {
+            format!("`{}` can't have children outside of its slots {}",
+                    name_str,
+                    slot_names.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", "))
+        };
+        Some(quote!{
+            if let Some(child) = node.children().first() {
+                return Err(child.error(#err));
+            }
+        })
+    };
+    let unknown_slot = format!("`{}` has no slot `{{}}`", name_str);
+    let known_slots = slot_names.iter().map(|name| quote!{#name => {}});
+    let unknown_slots = quote!{
+        for slot in node.slots() {
+            match &slot.name[..] {
+                #(#known_slots)*
+                name => return Err(slot.error(format!(#unknown_slot, name))),
+            }
+        }
+    };
+    let slot_args = node.slots.iter().map(|slot| gen_slot_from_template(node, slot));
+    let args = params_arg.into_iter().chain(slot_args);
+
+    let wrong_name = format!("expected `{}`, got `{{}}`", name_str);
+    let text = format!("expected `{}`, got text", name_str);
+    quote!{
+        #(#cfgs)*
+        impl ::vtree::template::FromTemplate for super::#name {
+            fn from_template(node: &::vtree::template::Node)
+                -> ::std::result::Result<super::#name, ::vtree::template::TemplateError>
+            {
+                match node.name() {
+                    Some(#name_str) => {}
+                    Some(name) => return Err(node.error(format!(#wrong_name, name))),
+                    None => return Err(node.error(#text)),
+                }
+                #params
+                #no_children
+                #unknown_slots
+                Ok(super::#name::new(#(#args),*))
+            }
+        }
+    }
+}
+
+/// `FromTemplate` for every node, constructing its params from the template params through
+/// `TemplateParams` and its `children` from the template children.
+pub fn gen_node_from_template_impls<'a>(pd: &'a ParsedData) -> impl Iterator<Item = Tokens> + 'a {
+    pd.normal_nodes().map(gen_node_from_template_impl)
+}
+
+/// `FromTemplate` for a group, dispatching on the name of the template node.
+pub fn gen_group_from_template_impl<'a, IT>(group: &Ident, nodes: IT, pd: &ParsedData) -> Tokens
+    where IT: Iterator<Item = &'a Node>
+{
+    let nodes: Vec<_> = nodes.collect();
+    let group_str = group.as_ref();
+    let members = nodes.iter().filter_map(|node| node.normal()).map(|node| {
+        let name = &node.name;
+        let name_str = name.as_ref();
+        let cfgs = node.cfg_attrs();
+        quote!{
+            #(#cfgs)*
+            Some(#name_str) => {
+                ::vtree::template::FromTemplate::from_template(node).map(#group::#name)
+            }
+        }
+    });
+    let text = if nodes.iter().any(|node| node.normal().is_none()) {
+        quote!{
+            None => ::vtree::template::FromTemplate::from_template(node).map(#group::Text),
+        }
+    } else {
+        let err = format!("text is not in group `{}`", group_str);
+        quote!{
+            None => Err(node.error(#err)),
+        }
+    };
+    let others = pd.normal_nodes()
+        .filter(|node| !nodes.iter().any(|n| n.normal().map(|n| &n.name) == Some(&node.name)))
+        .map(|node| {
+            let name_str = node.name.as_ref();
+            let cfgs = node.cfg_attrs();
+            let err = format!("`{}` is not in group `{}`", name_str, group_str);
+            quote!{
+                #(#cfgs)*
+                Some(#name_str) => Err(node.error(#err)),
+            }
+        });
+
+    quote!{
+        impl ::vtree::template::FromTemplate for #group {
+            fn from_template(node: &::vtree::template::Node)
+                -> ::std::result::Result<#group, ::vtree::template::TemplateError>
+            {
+                match node.name() {
+                    #(#members)*
+                    #(#others)*
+                    #text
+                    Some(name) => Err(node.error(format!("unknown node `{}`", name))),
+                }
+            }
+        }
+    }
+}
+
+pub fn gen_all_nodes_template_root_impl() -> Tokens {
+    quote!{
+        impl ::vtree::template::TemplateRoot for AllNodes {
+            fn expand_widgets(
+                curr: &mut AllNodes,
+                last: ::std::option::Option<&AllNodes>,
+                path: &::vtree::diff::SimplePathFrame,
+            ) {
+                AllNodes::expand_widgets(curr, last, path)
+            }
+
+            fn diff<CTX, D>(
+                curr: &::vtree::diff::PathFrame<AllNodes>,
+                last: &::vtree::diff::PathFrame<AllNodes>,
+                ctx: &mut ::vtree::diff::Context<CTX, AllNodes>,
+                differ: &mut D,
+            )
+                where D: ::vtree::diff::Differ<CTX, AllNodes>
+            {
+                AllNodes::diff(curr, last, ctx, differ)
+            }
+        }
+    }
+}
+
+/// `MarkupNode` for `AllNodes` printing the params differing from their defaults, with
+/// `to_markup` and `Display` printing the tree through `vtree::template::Markup`.
+pub fn gen_all_nodes_markup_node_impl(pd: &ParsedData) -> Tokens {
+    let variants = pd.nodes().map(|node| {
+        match *node {
+            Node::Normal(ref node) => {
+                let name = &node.name;
+                let name_str = name.as_ref();
+                let cfgs = node.cfg_attrs();
+                let params = match node.params_ty {
+                    Some(_) => {
+                        quote!{
+                            ::vtree::template::changed_markup_params(&node.params)
+                        }
+                    }
+                    None => quote!{::std::vec::Vec::new()},
+                };
+                quote!{
+                    #(#cfgs)*
+                    AllNodes::#name(ref node) => {
+                        ::vtree::template::MarkupHead::Node {
+                            name: #name_str,
+                            params: #params,
+                        }
+                    }
+                }
+            }
+            Node::Text => {
+                quote!{
+                    AllNodes::Text(ref text) => ::vtree::template::MarkupHead::Text(text),
+                }
+            }
+        }
+    });
+
+    quote!{
+        impl ::vtree::template::MarkupNode for AllNodes {
+            fn markup_head(&self) -> ::vtree::template::MarkupHead {
+                match *self {
+                    #(#variants)*
+                    AllNodes::Widget(_) => ::vtree::template::MarkupHead::Widget,
+                }
+            }
+        }
+
+        impl AllNodes {
+            /// The tree in `markup!` syntax.
+            pub fn to_markup(&self) -> ::std::string::String {
+                ::vtree::template::Markup(self).to_string()
+            }
+        }
+
+        impl ::std::fmt::Display for AllNodes {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&::vtree::template::Markup(self), f)
+            }
+        }
+    }
+}
+
+fn gen_children_schema(slot: &Slot, pd: &ParsedData) -> Tokens {
+    let kind = match slot.ty {
+        ChildType::Single => quote!{::vtree::template::ChildrenKind::Single},
+        ChildType::Optional => quote!{::vtree::template::ChildrenKind::Optional},
+        ChildType::Multi => quote!{::vtree::template::ChildrenKind::Multi},
+    };
+    let nodes: Vec<&Node> = match slot.child {
+        Child::Node(ref name) => pd.node_by_name(name).into_iter().collect(),
+        Child::Group(ref group) => {
+            pd.groups_nodes()
+                .find(|&(name, _)| name == group)
+                .map(|(_, nodes)| nodes.collect())
+                .unwrap_or_default()
+        }
+    };
+    let text = nodes.iter().any(|node| node.normal().is_none());
+    let pushes = nodes.iter().filter_map(|node| node.normal()).map(|node| {
+        let name_str = node.name.as_ref();
+        let cfgs = node.cfg_attrs();
+        quote!{
+            #(#cfgs)*
+            nodes.push(#name_str);
+        }
+    });
+    quote!{{
+        #[allow(unused_mut)]
+        let mut nodes = ::std::vec::Vec::new();
+        #(#pushes)*
+        ::vtree::template::ChildrenSchema {
+            kind: #kind,
+            nodes: nodes,
+            text: #text,
+        }
+    }}
+}
+
+/// `TemplateSchema` for `AllNodes`, listing the nodes with the children allowed by their
+/// `children` and named slots.
+pub fn gen_all_nodes_template_schema_impl(pd: &ParsedData) -> Tokens {
+    let nodes = pd.normal_nodes().map(|node| {
+        let name_str = node.name.as_ref();
+        let cfgs = node.cfg_attrs();
+        let params = match node.params_ty {
+            Some(ref params_ty) => {
+                quote!{
+                    <#params_ty as ::vtree::template::TemplateParams>::template_param_schema()
+                }
+            }
+            None => quote!{::std::vec::Vec::new()},
+        };
+        let children = match node.slots.iter().find(|slot| slot.name.is_none()) {
+            Some(slot) => {
+                let children = gen_children_schema(slot, pd);
+                quote!{::std::option::Option::Some(#children)}
+            }
+            None => quote!{::std::option::Option::None},
+        };
+        let slots = node.slots.iter().filter_map(|slot| {
+            let name_str = slot.name.as_ref()?.as_ref();
+            let children = gen_children_schema(slot, pd);
+            Some(quote!{
+                ::vtree::template::SlotSchema {
+                    name: #name_str,
+                    children: #children,
+                },
+            })
+        });
+        quote!{
+            #(#cfgs)*
+            schema.push(::vtree::template::NodeSchema {
+                name: #name_str,
+                params: #params,
+                children: #children,
+                slots: vec![#(#slots)*],
+            });
+        }
+    });
+
+    quote!{
+        impl ::vtree::template::TemplateSchema for AllNodes {
+            fn template_schema() -> ::std::vec::Vec<::vtree::template::NodeSchema> {
+                let mut schema = ::std::vec::Vec::new();
+                #(#nodes)*
+                schema
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 #![feature(conservative_impl_trait)]
 #![feature(proc_macro)]
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 
 #[macro_use]
 extern crate quote;
@@ -9,6 +9,8 @@ extern crate syn;
 extern crate synom;
 extern crate proc_macro;
 
+mod error;
+mod span;
 mod parser;
 mod generator;
 mod params;
@@ -20,11 +22,10 @@ use params::handle_params;
 
 #[proc_macro]
 pub fn define_nodes(input: TokenStream) -> TokenStream {
-    let input = input.to_string();
-    let pd = parse(&input);
-    for param_ty in pd.normal_nodes().filter_map(|node| node.params_ty.as_ref()) {
-        assert!(param_ty.global, "`{}` is not a global module Path", quote!{#param_ty});
-    }
+    let pd = match parse(&input.to_string()) {
+        Ok(pd) => pd,
+        Err(err) => return err.to_compile_error(&input),
+    };
     generate_defs(pd).parse().unwrap()
 }
 
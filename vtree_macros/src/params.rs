@@ -1,17 +1,536 @@
 use quote::Tokens;
-use syn::Ident;
-use syn::{self, parse_derive_input, Body, VariantData, Field, Attribute, MetaItem, Lit};
+use syn::{Generics, Ident};
+use syn::{self, parse_derive_input, Body, VariantData, Field, Attribute, MetaItem, Lit,
+          NestedMetaItem};
 
 fn field_is_event(field: &Field) -> bool {
     field.attrs.iter().any(|attr| attr.name() == "event")
 }
 
-fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
+/// Path of a string attribute like `#[validate = "path::to::fn"]`.
+fn path_attr(attrs: &[Attribute], name: &str) -> Option<syn::Path> {
+    match attrs.iter().find(|attr| attr.name() == name) {
+        Some(&Attribute {
+            value: MetaItem::NameValue(_, Lit::Str(ref path, _)),
+            ..
+        }) => {
+            Some(syn::parse_path(path).unwrap_or_else(|err| {
+                panic!("`{}` attribute has to hold a path: {}", name, err)
+            }))
+        }
+        Some(_) => panic!("`{}` attribute has to hold a string", name),
+        None => None,
+    }
+}
+
+fn field_is_required(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.name() == "required")
+}
+
+/// Type-state marker of the unset `#[required]` field, e.g. `ImageParamsMissingSrc`.
+fn missing_ident(name: &Ident, field_name: &Ident) -> Ident {
+    let mut missing = format!("{}Missing", name);
+    for part in field_name.as_ref().split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            missing.extend(first.to_uppercase());
+            missing.push_str(chars.as_str());
+        }
+    }
+    Ident::new(missing)
+}
+
+/// `DefaultParams` for params deriving `Default`, unless they have `#[required]` fields, which
+/// their `Default` can't know.
+/// How the builders fill a field which wasn't set, the `#[default]` value or the type's `Default`.
+/// `#[required]` fields are always set.
+fn gen_unset_field(field: &Field) -> Tokens {
+    let default = field.attrs
+        .iter()
+        .filter_map(|a| match a {
+            &Attribute {
+                value: MetaItem::NameValue(ref id, ref lit),
+                ..
+            } if id == "default" => Some(lit),
+            _ => None,
+        })
+        .next();
+    match default {
+        Some(lit) => quote!{
+            unwrap_or(#lit.into())
+        },
+        None => quote!{
+            unwrap_or_default()
+        },
+    }
+}
+
+fn gen_default_params_impl(name: &Ident, generics: &Generics, attrs: &[Attribute],
+                           fields: &[Field]) -> Option<Tokens> {
+    if fields.iter().any(field_is_required) {
+        return None;
+    }
+    let derives_default = attrs.iter().any(|attr| match attr.value {
+        MetaItem::List(ref id, ref items) if id == "derive" => {
+            items.iter().any(|item| match *item {
+                NestedMetaItem::MetaItem(MetaItem::Word(ref id)) => id == "Default",
+                _ => false,
+            })
+        }
+        _ => false,
+    });
+    if !derives_default {
+        return None;
+    }
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    // the derived `Default` of generic params bounds their type parameters by `Default`
+    let where_predicates = &generics.where_clause.predicates;
+    Some(quote!{
+        impl #impl_generics ::vtree::node::DefaultParams for #name #ty_generics
+            where #(#where_predicates,)*
+                  #name #ty_generics: ::std::default::Default
+        {
+            fn default_params() -> #name #ty_generics {
+                ::std::default::Default::default()
+            }
+        }
+    })
+}
+
+fn gen_layout_params_impl(name: &Ident, generics: &Generics, fields: &[Field]) -> Tokens {
+    let mut layout_fields = fields.iter().filter(|f| f.attrs.iter().any(|a| a.name() == "layout"));
+    let style = match layout_fields.next() {
+        Some(field) => {
+            let field_name = field.ident.as_ref().unwrap();
+            quote!{
+                ::std::clone::Clone::clone(&self.#field_name)
+            }
+        }
+        None => {
+            quote!{
+                ::std::default::Default::default()
+            }
+        }
+    };
+    if layout_fields.next().is_some() {
+        panic!("params macro: only one field can be marked with `layout`");
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!{
+        impl #impl_generics ::vtree::layout::LayoutParams for #name #ty_generics #where_clause {
+            fn layout_style(&self) -> ::vtree::layout::Style {
+                #style
+            }
+        }
+    }
+}
+
+fn gen_focus_params_impl(name: &Ident, generics: &Generics, fields: &[Field]) -> Tokens {
+    let mut focusable_fields =
+        fields.iter().filter(|f| f.attrs.iter().any(|a| a.name() == "focusable"));
+    let focusable = match focusable_fields.next() {
+        Some(field) => {
+            let field_name = field.ident.as_ref().unwrap();
+            quote!{
+                self.#field_name
+            }
+        }
+        None => quote!{false},
+    };
+    if focusable_fields.next().is_some() {
+        panic!("params macro: only one field can be marked with `focusable`");
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!{
+        impl #impl_generics ::vtree::focus::FocusParams for #name #ty_generics #where_clause {
+            fn focusable(&self) -> bool {
+                #focusable
+            }
+        }
+    }
+}
+
+fn gen_a11y_params_impl(name: &Ident, generics: &Generics, fields: &[Field]) -> Tokens {
+    let mut label = None;
+    let mut value = None;
+    let mut description = None;
+    for field in fields {
+        let kind = match field.attrs.iter().find(|attr| attr.name() == "a11y") {
+            Some(&Attribute {
+                value: MetaItem::NameValue(_, Lit::Str(ref kind, _)),
+                ..
+            }) => kind.as_str(),
+            Some(_) => panic!("`a11y` attribute has to hold a string"),
+            None => continue,
+        };
+        let slot = match kind {
+            "label" => &mut label,
+            "value" => &mut value,
+            "description" => &mut description,
+            kind => {
+                panic!("`a11y` attribute has to be `label`, `value` or `description`, got `{}`",
+                       kind)
+            }
+        };
+        if slot.is_some() {
+            panic!("params macro: only one field can be marked with `a11y = \"{}\"`", kind);
+        }
+        *slot = field.ident.as_ref();
+    }
+
+    let prop = |field_name: Option<&Ident>| match field_name {
+        Some(field_name) => {
+            quote!{
+                ::std::option::Option::Some(::std::string::ToString::to_string(&self.#field_name))
+            }
+        }
+        None => quote!{::std::option::Option::None},
+    };
+    let label = prop(label);
+    let value = prop(value);
+    let description = prop(description);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!{
+        impl #impl_generics ::vtree::a11y::AccessibleParams for #name #ty_generics #where_clause {
+            fn a11y_props(&self) -> ::vtree::a11y::A11yProps {
+                ::vtree::a11y::A11yProps {
+                    name: #label,
+                    value: #value,
+                    description: #description,
+                }
+            }
+        }
+    }
+}
+
+fn gen_animated_params_impl(name: &Ident, generics: &Generics, fields: &[Field]) -> Tokens {
+    let animated_fields: Vec<_> = fields
+        .iter()
+        .filter(|f| f.attrs.iter().any(|a| a.name() == "animate"))
+        .map(|f| f.ident.as_ref().unwrap())
+        .collect();
+
+    let values = animated_fields.iter().map(|field_name| {
+        quote!{
+            self.#field_name as f64,
+        }
+    });
+
+    let set_values = animated_fields.iter().enumerate().map(|(index, field_name)| {
+        quote!{
+            self.#field_name = values[#index] as _;
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!{
+        impl #impl_generics ::vtree::transition::AnimatedParams for #name #ty_generics #where_clause {
+            fn animated_values(&self) -> ::std::vec::Vec<f64> {
+                vec![#(#values)*]
+            }
+
+            #[allow(unused_variables)]
+            fn set_animated_values(&mut self, values: &[f64]) {
+                #(#set_values)*
+            }
+        }
+    }
+}
+
+fn gen_template_params_impl(name: &Ident, generics: &Generics, fields: &[Field]) -> Tokens {
+    let setters = fields.iter().map(|field| {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_str = field_name.as_ref();
+        if field_is_event(field) {
+            let err = format!("`{}` is an event and can't be set from a template", field_str);
+            return quote!{
+                #field_str => return Err(#err.to_string()),
+            };
+        }
+        quote!{
+            #field_str => {
+                self.#field_name = ::vtree::template::FromValue::from_value(value)
+                    .map_err(|err| format!("`{}`: {}", #field_str, err))?;
+            }
+        }
+    });
+    let name_str = name.as_ref();
+    // the values of the fields set by the template node, in variables named after the fields
+    let value_idents: Vec<Ident> = fields
+        .iter()
+        .map(|field| format!("{}_", field.ident.as_ref().unwrap()).into())
+        .collect();
+    let values = fields.iter().zip(&value_idents).filter(|&(field, _)| !field_is_event(field));
+    let value_decls = values.clone().map(|(field, value)| {
+        let ty = &field.ty;
+        quote!{
+            let mut #value: ::std::option::Option<#ty> = ::std::option::Option::None;
+        }
+    });
+    let value_setters = fields.iter().zip(&value_idents).map(|(field, value)| {
+        let field_str = field.ident.as_ref().unwrap().as_ref();
+        if field_is_event(field) {
+            let err = format!("`{}` is an event and can't be set from a template", field_str);
+            return quote!{
+                #field_str => {
+                    return Err(::vtree::template::TemplateError::new(#err, param.position));
+                }
+            };
+        }
+        quote!{
+            #field_str => {
+                #value = ::std::option::Option::Some(
+                    ::vtree::template::FromValue::from_value(&param.value).map_err(|err| {
+                        ::vtree::template::TemplateError::new(
+                            format!("`{}`: {}", #field_str, err),
+                            param.position,
+                        )
+                    })?
+                );
+            }
+        }
+    });
+    let value_fields = values.clone().map(|(field, value)| {
+        let field_name = field.ident.as_ref().unwrap();
+        if field_is_required(field) {
+            let err = format!("missing the required field `{}::{}`", name_str, field_name);
+            return quote!{
+                #field_name: match #value {
+                    ::std::option::Option::Some(value) => value,
+                    ::std::option::Option::None => return Err(node.error(#err)),
+                },
+            };
+        }
+        let unwrap = gen_unset_field(field);
+        quote!{
+            #field_name: #value.#unwrap,
+        }
+    });
+    let maybe_events_field = if fields.iter().any(|f| !field_is_event(f)) {
+        Some(quote!{
+            events_: ::vtree::node::ParamsEventsWrapper(::std::option::Option::None),
+        })
+    } else {
+        None
+    };
+    let schema = values.map(|(field, _)| {
+        let field_str = field.ident.as_ref().unwrap().as_ref();
+        let ty = &field.ty;
+        let required = field_is_required(field);
+        quote!{
+            ::vtree::template::ParamSchema {
+                name: #field_str,
+                values: <#ty as ::vtree::template::FromValue>::sample_values(),
+                required: #required,
+            },
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!{
+        impl #impl_generics ::vtree::template::TemplateParams for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn set_template_param(&mut self, name: &str, value: &::vtree::template::Value)
+                -> ::std::result::Result<(), ::std::string::String>
+            {
+                match name {
+                    #(#setters)*
+                    _ => return Err(format!("`{}` has no field `{}`", #name_str, name)),
+                }
+                Ok(())
+            }
+
+            fn from_template_params(node: &::vtree::template::Node)
+                -> ::std::result::Result<#name #ty_generics, ::vtree::template::TemplateError>
+            {
+                #(#value_decls)*
+                for param in node.params() {
+                    match &param.name[..] {
+                        #(#value_setters)*
+                        name => {
+                            return Err(::vtree::template::TemplateError::new(
+                                format!("`{}` has no field `{}`", #name_str, name),
+                                param.position,
+                            ));
+                        }
+                    }
+                }
+                Ok(#name {
+                    #(#value_fields)*
+                    #maybe_events_field
+                })
+            }
+
+            fn template_param_schema() -> ::std::vec::Vec<::vtree::template::ParamSchema> {
+                vec![#(#schema)*]
+            }
+        }
+    }
+}
+
+fn gen_markup_params_impl(name: &Ident, generics: &Generics, fields: &[Field]) -> Tokens {
+    let params = fields.iter().filter(|field| !field_is_event(field)).map(|field| {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_str = field_name.as_ref();
+        quote!{
+            if let Some(value) = ::vtree::template::ToMarkupValue::to_markup_value(&self.#field_name) {
+                params.push((#field_str, value));
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!{
+        impl #impl_generics ::vtree::template::MarkupParams for #name #ty_generics #where_clause {
+            fn markup_params(&self) -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+                #[allow(unused_mut)]
+                let mut params = ::std::vec::Vec::new();
+                #(#params)*
+                params
+            }
+        }
+    }
+}
+
+/// `CheckParams` running the `#[validate]` hooks of the fields, then the `#[finalize]` hook.
+fn gen_check_params_impl(name: &Ident, generics: &Generics, fields: &[Field],
+                         finalize: Option<&syn::Path>) -> Tokens {
+    let name_str = name.as_ref();
+    let validates = fields.iter().filter(|f| !field_is_event(f)).filter_map(|field| {
+        let validate = path_attr(&field.attrs, "validate")?;
+        let field_name = field.ident.as_ref().unwrap();
+        let field_name_str = field_name.as_ref();
+        Some(quote!{
+            params.#field_name = #validate(params.#field_name).map_err(|message| {
+                ::vtree::node::ParamsError {
+                    params: #name_str,
+                    field: ::std::option::Option::Some(#field_name_str),
+                    message: message,
+                }
+            })?;
+        })
+    });
+    let maybe_finalize = finalize.map(|finalize| {
+        quote!{
+            let params = #finalize(params).map_err(|message| {
+                ::vtree::node::ParamsError {
+                    params: #name_str,
+                    field: ::std::option::Option::None,
+                    message: message,
+                }
+            })?;
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!{
+        impl #impl_generics ::vtree::node::CheckParams for #name #ty_generics #where_clause {
+            fn check_params(self)
+                -> ::std::result::Result<#name #ty_generics, ::vtree::node::ParamsError>
+            {
+                #[allow(unused_mut)]
+                let mut params = self;
+                #(#validates)*
+                #maybe_finalize
+                ::std::result::Result::Ok(params)
+            }
+        }
+    }
+}
+
+fn gen_builder(name: &Ident, generics: &Generics, fields: &[Field], event_ty: &Tokens) -> Tokens {
     let builder_name: Ident = format!("{}Builder", name.as_ref()).into();
     let events_name: Ident = format!("{}Events", name.as_ref()).into();
     let events_name_str = events_name.as_ref();
     let has_events = fields.into_iter().any(|f| !field_is_event(f));
 
+    let required: Vec<_> = fields.iter().filter(|f| field_is_required(f)).collect();
+    for field in &required {
+        let field_name = field.ident.as_ref().unwrap();
+        if field_is_event(field) {
+            panic!("params macro: event field `{}` can't be `required`", field_name);
+        }
+        if field.attrs.iter().any(|a| a.name() == "default") {
+            panic!("params macro: required field `{}` can't have a `default`", field_name);
+        }
+    }
+    // type parameters tracking the required fields
+    let required_params: Vec<Ident> =
+        (0..required.len()).map(|i| format!("R{}", i).into()).collect();
+    let missing_idents: Vec<Ident> = required
+        .iter()
+        .map(|f| missing_ident(name, f.ident.as_ref().unwrap()))
+        .collect();
+    // the struct's lifetimes, `PB`, the struct's type parameters and the given parameters,
+    // either with their bounds for impl headers or as arguments
+    let builder_generics = |bounds: bool, params: &[Tokens]| {
+        let lifetimes = generics.lifetimes.iter().map(|l| if bounds {
+            quote!{#l}
+        } else {
+            let lifetime = &l.lifetime;
+            quote!{#lifetime}
+        });
+        let ty_params = generics.ty_params.iter().map(|t| if bounds {
+            let mut t = t.clone();
+            t.default = None;
+            quote!{#t}
+        } else {
+            let ident = &t.ident;
+            quote!{#ident}
+        });
+        quote!{#(#lifetimes,)* PB #(, #ty_params)* #(, #params)*}
+    };
+    let required_tokens: Vec<_> = required_params.iter().map(|p| quote!{#p}).collect();
+    let pb_params = builder_generics(true, &[]);
+    let pb_generics = builder_generics(false, &[]);
+    let state_params = builder_generics(true, &required_tokens);
+    let state_generics = builder_generics(false, &required_tokens);
+    let missing_generics =
+        builder_generics(false, &missing_idents.iter().map(|p| quote!{#p}).collect::<Vec<_>>());
+    let defaulted_params = builder_generics(
+        true,
+        &required_params
+            .iter()
+            .zip(missing_idents.iter())
+            .map(|(p, m)| quote!{#p = #m})
+            .collect::<Vec<_>>(),
+    );
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let name_ty = quote!{#name #ty_generics};
+    let where_predicates = &generics.where_clause.predicates;
+    let where_predicates = quote!{#(#where_predicates,)*};
+    let struct_where_clause = if generics.where_clause.predicates.is_empty() {
+        None
+    } else {
+        Some(quote!{where #where_predicates})
+    };
+    let missing_defs = missing_idents.iter().map(|missing| {
+        quote!{
+            pub struct #missing;
+        }
+    });
+    let maybe_required_field = if required.is_empty() {
+        None
+    } else {
+        let required_params = &required_params;
+        Some(quote!{
+            required_: ::std::marker::PhantomData<(#(#required_params,)*)>,
+        })
+    };
+    let maybe_required_constr = maybe_required_field.as_ref().map(|_| {
+        quote!{
+            required_: ::std::marker::PhantomData,
+        }
+    });
+    let required_bounds = required_params.iter().map(|p| {
+        quote!{
+            #p: ::vtree::node::IsRequiredSet,
+        }
+    });
+
     let struct_fields = fields
         .into_iter()
         .filter(|f| !field_is_event(f))
@@ -28,29 +547,25 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
         .filter(|f| !field_is_event(f))
         .map(|field| {
             let field_name = field.ident.as_ref().unwrap();
-            let unwrap = field.attrs
-                .iter()
-                .filter_map(|a| match a {
-                    &Attribute {
-                        value: MetaItem::NameValue(ref id, ref lit),
-                        ..
-                    } if id == "default" => Some(lit),
-                    _ => None,
-                })
-                .next();
-            let unwrap = match unwrap {
-                Some(lit) => quote!{
-                    unwrap_or(#lit.into())
-                },
-                None => quote!{
-                    unwrap_or_default()
-                },
+            let unwrap = if field_is_required(field) {
+                // set according to the type-state
+                quote!{
+                    unwrap()
+                }
+            } else {
+                gen_unset_field(field)
             };
             quote! {
                 #field_name: self.#field_name.#unwrap,
             }
         });
 
+    let moved_fields: Vec<_> = fields
+        .iter()
+        .filter(|f| !field_is_event(f))
+        .map(|f| f.ident.as_ref().unwrap())
+        .collect();
+
     let setters_getters = fields
         .into_iter()
         .map(|field| {
@@ -59,9 +574,58 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
             let setter_name: Ident = format!("set_{}", field_name.as_ref()).into();
             let mut_getter_name: Ident = format!("mut_{}", field_name.as_ref()).into();
 
+            let setter = if let Some(index) = required.iter().position(|f| f.ident == field.ident) {
+                // moves the fields into the builder with the field marked as set
+                let set_generics = builder_generics(
+                    false,
+                    &required_params
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| if i == index {
+                            quote!{::vtree::node::RequiredSet}
+                        } else {
+                            quote!{#p}
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                let moves = moved_fields.iter().map(|moved| if *moved == field_name {
+                    quote!{
+                        #moved: ::std::option::Option::Some(value),
+                    }
+                } else {
+                    quote!{
+                        #moved: self.#moved,
+                    }
+                });
+                let maybe_events_move = if has_events {
+                    Some(quote!{
+                        events_: self.events_,
+                    })
+                } else {
+                    None
+                };
+                quote! {
+                    pub fn #setter_name(self, value: #ty) -> #builder_name<#set_generics> {
+                        #builder_name {
+                            parent_builder_: self.parent_builder_,
+                            #maybe_events_move
+                            #(#moves)*
+                            required_: ::std::marker::PhantomData,
+                        }
+                    }
+                }
+            } else {
+                quote! {
+                    pub fn #setter_name(mut self, value: #ty) -> #builder_name<#state_generics> {
+                        self.#field_name = ::std::option::Option::Some(value);
+                        self
+                    }
+                }
+            };
+
             if field_is_event(field) {
                 quote! {
-                    pub fn #setter_name<F>(mut self, f: F) -> #builder_name<PB>
+                    pub fn #setter_name<F>(mut self, f: F) -> #builder_name<#state_generics>
                         where F: Fn(#ty) + 'static
                     {
                         match self.events_ {
@@ -77,10 +641,7 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
                 }
             } else {
                 quote! {
-                    pub fn #setter_name(mut self, value: #ty) -> #builder_name<PB> {
-                        self.#field_name = ::std::option::Option::Some(value);
-                        self
-                    }
+                    #setter
 
                     pub fn #field_name(&self) -> ::std::option::Option<&#ty> {
                         self.#field_name.as_ref()
@@ -141,7 +702,7 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
                 let field_name = field.ident.as_ref().unwrap();
                 let field_name_str = field_name.as_ref();
                 Some(quote! {
-                    (#field_name_str, AllEvent::#ty(e)) => {
+                    (#field_name_str, #event_ty::#ty(e)) => {
                         if let ::std::option::Option::Some(ref mut h) = self.#field_name {
                             h(e);
                         }
@@ -155,7 +716,7 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
                 #(#event_fields)*
             }
 
-            impl ::vtree::node::ParamsEvents<AllEvent> for #events_name {
+            impl ::vtree::node::ParamsEvents<#event_ty> for #events_name {
                 fn has(&self, event_name: &str) -> bool {
                     match event_name {
                         #(#has_event_variants)*
@@ -163,7 +724,7 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
                     }
                 }
 
-                fn send(&mut self, event_name: &str, event: AllEvent) {
+                fn send(&mut self, event_name: &str, event: #event_ty) {
                     match (event_name, event) {
                         #(#send_event_variants)*
                         (event_name, event) => {
@@ -213,41 +774,68 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
     };
 
     quote!{
-        impl <PB> ::vtree::node::Params<PB> for #name
-            where PB: ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #name>
+        impl <#pb_params> ::vtree::node::Params<PB> for #name_ty
+            where #where_predicates
+                  PB: ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #name_ty>
         {
-            type Builder = #builder_name<PB>;
+            type Builder = #builder_name<#pb_generics>;
 
-            fn builder(parent_builder: PB) -> #builder_name<PB> {
+            fn builder(parent_builder: PB) -> #builder_name<#pb_generics> {
                 #builder_name::new(parent_builder)
             }
         }
 
-        pub struct #builder_name<PB> {
+        #(#missing_defs)*
+
+        pub struct #builder_name<#defaulted_params> #struct_where_clause {
             parent_builder_: PB,
             #maybe_struct_events_field
             #(#struct_fields)*
+            #maybe_required_field
         }
 
-        impl <PB> #builder_name<PB>
-            where PB: ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #name>
+        impl <#pb_params> #builder_name<#missing_generics>
+            where #where_predicates
+                  PB: ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #name_ty>
         {
-            pub fn new(parent_builder: PB) -> #builder_name<PB> {
+            pub fn new(parent_builder: PB) -> #builder_name<#missing_generics> {
                 #builder_name {
                     parent_builder_: parent_builder,
                     #maybe_constructor_events_field
                     #(#constructor_fields)*
+                    #maybe_required_constr
                 }
             }
+        }
 
+        impl <#state_params> #builder_name<#state_generics>
+            where #where_predicates
+                  PB: ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #name_ty>,
+                  #(#required_bounds)*
+        {
+            /// Panics if a `validate` or `finalize` hook rejects the params.
             pub fn build(self) -> PB {
+                match self.try_build() {
+                    ::std::result::Result::Ok(pb) => pb,
+                    ::std::result::Result::Err(err) => panic!("{}", err),
+                }
+            }
+
+            pub fn try_build(self) -> ::std::result::Result<PB, ::vtree::node::ParamsError> {
                 let mut pb = self.parent_builder_;
-                pb.builder_set(#name {
+                let params = #name {
                     #(#build_fields)*
                     #maybe_build_events_field
-                });
-                pb
+                };
+                pb.builder_set(::vtree::node::CheckParams::check_params(params)?);
+                ::std::result::Result::Ok(pb)
             }
+        }
+
+        impl <#state_params> #builder_name<#state_generics>
+            where #where_predicates
+                  PB: ::vtree::node::BuilderSetter<::vtree::node::BuilderParams, #name_ty>
+        {
             #(#setters_getters)*
         }
 
@@ -255,15 +843,44 @@ fn gen_builder(name: &Ident, fields: &[Field]) -> Tokens {
     }
 }
 
+/// Event enum set with `#[events = "::path::Event"]` on the struct, `AllEvent` by default.
+fn event_ty(attrs: &[Attribute]) -> Tokens {
+    match attrs.iter().find(|attr| attr.name() == "events") {
+        Some(&Attribute {
+            value: MetaItem::NameValue(_, Lit::Str(ref path, _)),
+            ..
+        }) => {
+            let path = syn::parse_path(path)
+                .unwrap_or_else(|err| panic!("`events` attribute has to hold a path: {}", err));
+            quote!{#path}
+        }
+        Some(_) => panic!("`events` attribute has to hold a string"),
+        None => quote!{AllEvent},
+    }
+}
+
 pub fn handle_params(input: String) -> String {
     let mut ast = parse_derive_input(&input).unwrap();
+    let event_ty = event_ty(&ast.attrs);
+    let finalize = path_attr(&ast.attrs, "finalize");
+    ast.attrs.retain(|a| a.name() != "events" && a.name() != "finalize");
     let builder = {
         let mut fields = match ast.body {
             Body::Struct(VariantData::Struct(ref mut fields)) => fields,
             Body::Struct(_) => panic!("params macro: units and tuples not supported"),
             Body::Enum(_) => panic!("params macro: enums not supported"),
         };
-        let builder = gen_builder(&ast.ident, &fields);
+        let builder = gen_builder(&ast.ident, &ast.generics, &fields, &event_ty);
+        let check_params_impl =
+            gen_check_params_impl(&ast.ident, &ast.generics, &fields, finalize.as_ref());
+        let default_params_impl =
+            gen_default_params_impl(&ast.ident, &ast.generics, &ast.attrs, &fields);
+        let layout_params_impl = gen_layout_params_impl(&ast.ident, &ast.generics, &fields);
+        let focus_params_impl = gen_focus_params_impl(&ast.ident, &ast.generics, &fields);
+        let a11y_params_impl = gen_a11y_params_impl(&ast.ident, &ast.generics, &fields);
+        let animated_params_impl = gen_animated_params_impl(&ast.ident, &ast.generics, &fields);
+        let template_params_impl = gen_template_params_impl(&ast.ident, &ast.generics, &fields);
+        let markup_params_impl = gen_markup_params_impl(&ast.ident, &ast.generics, &fields);
         let has_events = fields.into_iter().any(|f| !field_is_event(f));
         fields.retain(|f| !field_is_event(f));
         if has_events {
@@ -279,9 +896,22 @@ pub fn handle_params(input: String) -> String {
             });
         }
         for field in fields.iter_mut() {
-            field.attrs.retain(|a| !["default"].contains(&a.name()));
+            field.attrs.retain(|a| {
+                !["default", "required", "validate", "layout", "focusable", "a11y", "animate"]
+                    .contains(&a.name())
+            });
+        }
+        quote!{
+            #builder
+            #check_params_impl
+            #default_params_impl
+            #layout_params_impl
+            #focus_params_impl
+            #a11y_params_impl
+            #animated_params_impl
+            #template_params_impl
+            #markup_params_impl
         }
-        builder
     };
 
     quote!(
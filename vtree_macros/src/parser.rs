@@ -1,7 +1,10 @@
-use syn::{Ident, Path};
+use syn::{Ident, Path, Attribute, MetaItem, NestedMetaItem, Lit};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use parser::parser::{GroupOp};
+pub use parser::parser::{GroupOp, Group as GroupDef};
+use error::{Error, Pos};
+use synom::IResult;
+use synom::space::skip_whitespace;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ChildType {
@@ -10,17 +13,105 @@ pub enum ChildType {
     Multi,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Child {
     Node(Ident),
     Group(Ident),
 }
 
+#[derive(Debug, Clone)]
+pub struct Slot {
+    /// `None` for the `children` of a node without named slots.
+    pub name: Option<Ident>,
+    pub ty: ChildType,
+    pub child: Child,
+    /// Position of the slot name, or of the child for the `children`.
+    pub pos: Pos,
+    pub child_pos: Pos,
+}
+
+impl Slot {
+    /// Name of the field holding the children of this slot.
+    pub fn field(&self) -> Ident {
+        self.name.clone().unwrap_or_else(|| Ident::new("children"))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NodeNormal {
+    pub attrs: Vec<Attribute>,
     pub name: Ident,
+    pub pos: Pos,
+    /// Generic params structs are instantiated here, e.g. `::ListParams<i64>`.
     pub params_ty: Option<Path>,
-    pub child: Option<(ChildType, Child)>,
+    pub params_pos: Option<Pos>,
+    pub slots: Vec<Slot>,
+}
+
+impl NodeNormal {
+    /// Attributes forwarded to the generated node struct, all but `#[role]`.
+    pub fn forwarded_attrs<'a>(&'a self) -> impl Iterator<Item = &'a Attribute> + 'a {
+        self.attrs.iter().filter(|attr| attr.name() != "role")
+    }
+
+    /// `#[cfg]` attributes, which are applied to everything generated for the node.
+    pub fn cfg_attrs(&self) -> Vec<&Attribute> {
+        self.attrs.iter().filter(|attr| attr.name() == "cfg").collect()
+    }
+
+    /// Whether `trait_name` is derived with `#[derive(...)]`.
+    pub fn derives(&self, trait_name: &str) -> bool {
+        derives(&self.attrs, trait_name)
+    }
+
+    /// Accessibility role set with `#[role = "..."]`.
+    pub fn role(&self) -> Option<&str> {
+        self.attrs
+            .iter()
+            .filter_map(|a| match a.value {
+                MetaItem::NameValue(ref id, Lit::Str(ref role, _)) if id == "role" => {
+                    Some(role.as_str())
+                }
+                _ => None,
+            })
+            .next()
+    }
+}
+
+fn is_derived(item: &NestedMetaItem, trait_name: &str) -> bool {
+    match *item {
+        NestedMetaItem::MetaItem(MetaItem::Word(ref id)) => id == trait_name,
+        _ => false,
+    }
+}
+
+/// Whether `trait_name` is derived by one of `attrs`.
+fn derives(attrs: &[Attribute], trait_name: &str) -> bool {
+    attrs.iter().any(|attr| match attr.value {
+        MetaItem::List(ref id, ref items) if id == "derive" => {
+            items.iter().any(|item| is_derived(item, trait_name))
+        }
+        _ => false,
+    })
+}
+
+/// `attrs` without `trait_name` in their `#[derive(...)]`, for traits implemented by hand.
+fn strip_derive(attrs: &[Attribute], trait_name: &str) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .cloned()
+        .filter_map(|mut attr| {
+            if let MetaItem::List(ref id, ref mut items) = attr.value {
+                if id == "derive" {
+                    items.retain(|item| !is_derived(item, trait_name));
+                    if items.is_empty() {
+                        return None;
+                    }
+                }
+            }
+            Some(attr)
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone)]
@@ -36,18 +127,59 @@ impl Node {
             _ => None,
         }
     }
+
+    pub fn cfg_attrs(&self) -> Vec<&Attribute> {
+        match self {
+            &Node::Normal(ref n) => n.cfg_attrs(),
+            &Node::Text => Vec::new(),
+        }
+    }
+
+    pub fn derives(&self, trait_name: &str) -> bool {
+        match self {
+            &Node::Normal(ref n) => n.derives(trait_name),
+            &Node::Text => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Group {
     pub name: Ident,
+    /// Attributes forwarded to the generated group enum.
+    pub attrs: Vec<Attribute>,
     pub nodes: HashSet<Ident>,
 }
 
+/// One `nodes { ... } groups { ... }` pair, an invocation can hold several which are merged.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub nodes: Vec<NodeNormal>,
+    pub groups: Vec<GroupDef>,
+    /// Position of `fixpoint` in `groups(fixpoint)`.
+    pub fixpoint: Option<Pos>,
+    /// Whether the section follows the `export` line, its params paths are then relative to
+    /// the exporting crate.
+    pub own: bool,
+}
+
+/// `export name(::crate_path);`, generating a `name!` macro which defines the nodes of this
+/// invocation together with the ones passed to it. The macro defines them anew where it's
+/// invoked, so they are distinct types from the ones of the exporting crate, only the params
+/// types are shared through `crate_path`. It invokes `$crate::define_nodes!`, which the
+/// exporting crate re-exports with `pub use vtree_macros::define_nodes;` at its root.
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub name: Ident,
+    pub crate_path: Option<Path>,
+    pub sections: Vec<Section>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ParsedData {
     node_by_name: HashMap<Ident, Node>,
     groups: Vec<Group>,
+    pub export: Option<Export>,
 }
 
 impl ParsedData {
@@ -69,15 +201,26 @@ impl ParsedData {
     pub fn node_by_name<'a>(&'a self, name: &Ident) -> Option<&'a Node> {
         self.node_by_name.get(name)
     }
+
+    pub fn group_attrs(&self, name: &Ident) -> &[Attribute] {
+        self.groups
+            .iter()
+            .find(|group| group.name == *name)
+            .map(|group| &group.attrs[..])
+            .unwrap_or(&[])
+    }
 }
 
-fn resolve_groups(
-    groups: &HashMap<Ident, Vec<(GroupOp, Child)>>,
-    children: &[(GroupOp, Child)]
-) -> HashSet<Ident> {
+/// Upper bound of rounds for `groups(fixpoint)`, removals in cyclic groups can keep the sets
+/// from settling.
+const MAX_FIXPOINT_ROUNDS: usize = 1000;
+
+fn apply_group_children<F>(children: &[(GroupOp, Child, Pos)], mut resolve: F) -> Result<HashSet<Ident>, Error>
+    where F: FnMut(&Ident) -> Result<HashSet<Ident>, Error>
+{
     let mut set = HashSet::new();
     set.insert(Ident::new("Text"));
-    for &(op, ref child) in children {
+    for &(op, ref child, _) in children {
         match child {
             &Child::Node(ref id) => {
                 match op {
@@ -90,7 +233,7 @@ fn resolve_groups(
                 }
             }
             &Child::Group(ref id) => {
-                for child in resolve_groups(groups, &groups.get(id).unwrap()[..]) {
+                for child in resolve(id)? {
                     match op {
                         GroupOp::Add => {
                             set.insert(child);
@@ -103,14 +246,298 @@ fn resolve_groups(
             }
         }
     }
-    set
+    Ok(set)
+}
+
+/// Resolves the group `name` directly. Cycles are only allowed through the groups of
+/// `groups(fixpoint)` sections in `fixpoint_groups`, they are left out of the returned set and
+/// resolved by `resolve_groups_fixpoint`.
+fn resolve_group(
+    groups: &HashMap<Ident, Vec<(GroupOp, Child, Pos)>>,
+    fixpoint_groups: &HashSet<Ident>,
+    name: &Ident,
+    stack: &mut Vec<Ident>,
+) -> Result<HashSet<Ident>, Error> {
+    if let Some(pos) = stack.iter().position(|g| g == name) {
+        let cycle = &stack[pos..];
+        if cycle.iter().all(|g| fixpoint_groups.contains(g)) {
+            return Ok(HashSet::new());
+        }
+        let cycle: Vec<_> = cycle
+            .iter()
+            .chain(Some(name))
+            .map(|g| format!("@{}", g))
+            .collect();
+        return Err(Error::new(
+            format!(
+                "cyclic group definition: {}, use `groups(fixpoint) {{ ... }}` to allow cycles",
+                cycle.join(" -> ")
+            ),
+            reference_pos(&groups[&stack[stack.len() - 1]], name),
+        ));
+    }
+
+    stack.push(name.clone());
+    let set = apply_group_children(&groups[name], |id| {
+        resolve_group(groups, fixpoint_groups, id, stack)
+    })?;
+    stack.pop();
+    Ok(set)
+}
+
+fn resolve_groups(
+    groups: &HashMap<Ident, Vec<(GroupOp, Child, Pos)>>,
+    fixpoint_groups: &HashSet<Ident>,
+    names: &[Ident],
+) -> Result<HashMap<Ident, HashSet<Ident>>, Error> {
+    names
+        .iter()
+        .map(|name| {
+            resolve_group(groups, fixpoint_groups, name, &mut Vec::new())
+                .map(|set| (name.clone(), set))
+        })
+        .collect()
+}
+
+/// Resolves groups which may reference each other in cycles by iterating from empty groups
+/// until no group changes anymore. Groups outside of cycles settle on their direct resolution.
+fn resolve_groups_fixpoint(
+    groups: &HashMap<Ident, Vec<(GroupOp, Child, Pos)>>,
+    fixpoint: Pos,
+) -> Result<HashMap<Ident, HashSet<Ident>>, Error> {
+    let mut sets: HashMap<Ident, HashSet<Ident>> =
+        groups.keys().map(|name| (name.clone(), HashSet::new())).collect();
+    for _ in 0..MAX_FIXPOINT_ROUNDS {
+        let next = groups
+            .iter()
+            .map(|(name, children)| {
+                apply_group_children(children, |id| Ok(sets[id].clone()))
+                    .map(|set| (name.clone(), set))
+            })
+            .collect::<Result<HashMap<_, _>, Error>>()?;
+        if next == sets {
+            return Ok(sets);
+        }
+        sets = next;
+    }
+    Err(Error::new(
+        "groups don't settle on a fixpoint, check removals (`-`) in cyclic groups",
+        fixpoint,
+    ))
+}
+
+/// Position of the reference to the group `name` in `children`.
+fn reference_pos(children: &[(GroupOp, Child, Pos)], name: &Ident) -> Pos {
+    children
+        .iter()
+        .find(|&&(_, ref child, _)| match *child {
+            Child::Group(ref group) => group == name,
+            Child::Node(_) => false,
+        })
+        .map(|&(_, _, pos)| pos)
+        .expect("referenced group")
+}
+
+/// Position of the next token in `rest`.
+fn pos(rest: &str) -> Pos {
+    Pos(skip_whitespace(rest).len())
+}
+
+fn step<'a, O>(
+    rest: &'a str,
+    parser: fn(&'a str) -> IResult<&'a str, O>,
+    expected: &str,
+) -> Result<(&'a str, O), Error> {
+    match parser(rest) {
+        IResult::Done(rest, o) => Ok((rest, o)),
+        IResult::Error => {
+            let msg = if rest.trim().is_empty() {
+                format!("unexpected end of input, expected {}", expected)
+            } else {
+                format!("expected {}", expected)
+            };
+            Err(Error::new(msg, pos(rest)))
+        }
+    }
+}
+
+fn parse_syntax(input: &str) -> Result<(Vec<Section>, Option<(Ident, Option<Path>)>), Error> {
+    let mut sections = Vec::new();
+    let mut export = None;
+    let mut rest = input;
+    loop {
+        if let IResult::Done(after, (name, crate_path)) = parser::parse_export(rest) {
+            if export.is_some() {
+                return Err(Error::new("only one `export` is allowed per invocation", pos(rest)));
+            }
+            if let Some((crate_path_pos, ref crate_path)) = crate_path {
+                if !crate_path.global {
+                    return Err(Error::new(
+                        format!("export path `{}` is not a global path, use `::{}`",
+                                quote!{#crate_path}, quote!{#crate_path}),
+                        crate_path_pos,
+                    ));
+                }
+            }
+            export = Some((name, crate_path.map(|(_, crate_path)| crate_path)));
+            rest = after;
+            continue;
+        }
+        if !sections.is_empty() && rest.trim().is_empty() {
+            break;
+        }
+
+        let (r, _) = step(rest, parser::parse_nodes_start, "`nodes {` or `export`")?;
+        let (r, nodes) = step(r, parser::parse_nodes, "a node definition")?;
+        let (r, _) = step(r, parser::parse_close, "`,`, `}` or a node definition")?;
+        if r.trim().is_empty() {
+            return Err(Error::new(
+                "missing `groups { ... }` section after `nodes { ... }`",
+                pos(r),
+            ));
+        }
+        let (r, fixpoint) = step(r, parser::parse_groups_start, "`groups {`")?;
+        let (r, groups) = step(r, parser::parse_groups, "a group definition")?;
+        let (r, _) = step(r, parser::parse_close, "`,`, `}` or a group definition")?;
+        sections.push(Section {
+            nodes: nodes,
+            groups: groups,
+            fixpoint: fixpoint,
+            own: export.is_some(),
+        });
+        rest = r;
+    }
+    Ok((sections, export))
 }
 
-pub fn parse(input: &str) -> ParsedData {
-    // TODO: detect loops
-    // TODO: report errors
+/// Methods of the generated node builders, slots can't take their names.
+const RESERVED_SLOT_NAMES: &[&str] = &["new", "build", "builder", "set_params", "try_set_params"];
+
+fn validate(nodes: &[NodeNormal], groups: &[GroupDef]) -> Result<(), Error> {
+    let text_ident = Ident::new("Text");
+    let mut node_names = HashSet::new();
+    for node in nodes {
+        if node.name == text_ident || !node_names.insert(&node.name) {
+            return Err(Error::new(format!("duplicate node `{}`", node.name), node.pos));
+        }
+    }
+
+    let mut group_names = HashSet::new();
+    for group in groups {
+        if !group_names.insert(&group.name) {
+            return Err(Error::new(format!("duplicate group `{}`", group.name), group.pos));
+        }
+    }
+
+    let check_child = |child: &Child, pos: Pos| match child {
+        &Child::Node(ref name) if *name != text_ident && !node_names.contains(name) => {
+            Err(Error::new(format!("unknown node `{}`", name), pos))
+        }
+        &Child::Group(ref name) if !group_names.contains(name) => {
+            Err(Error::new(format!("unknown group `@{}`", name), pos))
+        }
+        _ => Ok(()),
+    };
 
-    let (nodes, groups) = parser::parse(input).expect("vtree define_nodes");
+    for node in nodes {
+        let mut slot_names = HashSet::new();
+        for slot in &node.slots {
+            check_child(&slot.child, slot.child_pos)?;
+            let name = match slot.name {
+                Some(ref name) => name,
+                None => continue,
+            };
+            if name == "params" {
+                return Err(Error::new(
+                    format!("slot `params` of node `{}` clashes with its params field", node.name),
+                    slot.pos,
+                ));
+            }
+            let builder_method = RESERVED_SLOT_NAMES.contains(&name.as_ref()) ||
+                node.slots.iter().any(|other| {
+                    other.name.as_ref().map_or(false, |other| *name == format!("set_{}", other))
+                });
+            if builder_method {
+                return Err(Error::new(
+                    format!("slot `{}` of node `{}` clashes with the builder's `{0}` method",
+                            name, node.name),
+                    slot.pos,
+                ));
+            }
+            if !slot_names.insert(name) {
+                return Err(Error::new(
+                    format!("duplicate slot `{}` in node `{}`", name, node.name),
+                    slot.pos,
+                ));
+            }
+        }
+        if let Some(ref params_ty) = node.params_ty {
+            // `A<T>` reads as a generic node, which `AllNodes` with a variant per node can't hold
+            let type_param = !params_ty.global && params_ty.segments.len() == 1 &&
+                params_ty.segments[0].parameters.is_empty();
+            if type_param {
+                return Err(Error::new(
+                    format!(
+                        "params type `{}` of node `{}` is not a global module path, nodes can't \
+                         be generic but can take instantiated generic params like \
+                         `::path::ListParams<i64>`",
+                        quote!{#params_ty},
+                        node.name
+                    ),
+                    node.params_pos.unwrap_or(node.pos),
+                ));
+            }
+            if !params_ty.global {
+                return Err(Error::new(
+                    format!(
+                        "params type `{}` of node `{}` is not a global module path, use `::{}`",
+                        quote!{#params_ty},
+                        node.name,
+                        quote!{#params_ty}
+                    ),
+                    node.params_pos.unwrap_or(node.pos),
+                ));
+            }
+        }
+        for attr in &node.attrs {
+            match attr.value {
+                MetaItem::NameValue(ref id, Lit::Str(..)) if id == "role" => {}
+                MetaItem::NameValue(ref id, _) if id == "role" => {
+                    return Err(Error::new(
+                        format!("`role` attribute of node `{}` has to hold a string", node.name),
+                        node.pos,
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for group in groups {
+        for &(_, ref child, pos) in &group.children {
+            check_child(child, pos)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn parse(input: &str) -> Result<ParsedData, Error> {
+    let (sections, export) = parse_syntax(input)?;
+    let fixpoint = sections.iter().filter_map(|section| section.fixpoint).next();
+    let fixpoint_groups: HashSet<Ident> = sections
+        .iter()
+        .filter(|section| section.fixpoint.is_some())
+        .flat_map(|section| section.groups.iter().map(|group| group.name.clone()))
+        .collect();
+    let nodes: Vec<_> = sections.iter().flat_map(|s| s.nodes.iter().cloned()).collect();
+    let groups: Vec<_> = sections.iter().flat_map(|s| s.groups.iter().cloned()).collect();
+    validate(&nodes, &groups)?;
+    let export = export.map(|(name, crate_path)| Export {
+        name: name,
+        crate_path: crate_path,
+        sections: sections,
+    });
 
     let mut nodes_by_name: HashMap<_, _> = nodes
         .iter()
@@ -118,34 +545,85 @@ pub fn parse(input: &str) -> ParsedData {
         .map(|n| (n.name.clone(), Node::Normal(n)))
         .collect();
 
-    let groups: HashMap<Ident, Vec<(GroupOp, Child)>> = groups
+    let group_names: Vec<_> = groups.iter().map(|g| g.name.clone()).collect();
+    // the group enums hold widgets, `PartialEq` is implemented by `define_nodes!`
+    let mut group_attrs: HashMap<Ident, Vec<Attribute>> = groups
+        .iter()
+        .map(|g| (g.name.clone(), strip_derive(&g.attrs, "PartialEq")))
+        .collect();
+    let partial_eq_groups: Vec<(Ident, Pos)> = groups
+        .iter()
+        .filter(|g| derives(&g.attrs, "PartialEq"))
+        .map(|g| (g.name.clone(), g.pos))
+        .collect();
+    let groups: HashMap<Ident, Vec<(GroupOp, Child, Pos)>> = groups
         .into_iter()
         .map(|g| (g.name, g.children))
         .collect();
 
-    let groups: Vec<Group> = groups
-        .iter()
-        .map(|(id, children)| Group {
-            name: id.clone(),
-            nodes: resolve_groups(&groups, children).into_iter().collect(),
+    // rejects the cycles through groups of other sections
+    let resolved = resolve_groups(&groups, &fixpoint_groups, &group_names)?;
+    let resolved = match fixpoint {
+        Some(fixpoint) => resolve_groups_fixpoint(&groups, fixpoint)?,
+        None => resolved,
+    };
+    let groups: Vec<Group> = resolved
+        .into_iter()
+        .map(|(name, nodes)| Group {
+            attrs: group_attrs.remove(&name).unwrap_or_default(),
+            name: name,
+            nodes: nodes,
         })
         .collect();
 
+    for &(ref name, pos) in &partial_eq_groups {
+        let group = groups.iter().find(|group| group.name == *name).expect("resolved group");
+        let mut missing: Vec<_> = group.nodes
+            .iter()
+            .filter(|node| nodes.iter().any(|n| n.name == **node && !n.derives("PartialEq")))
+            .map(|node| format!("`{}`", node))
+            .collect();
+        if !missing.is_empty() {
+            missing.sort();
+            let (nodes, verb) = if missing.len() == 1 {
+                ("node", "doesn't")
+            } else {
+                ("nodes", "don't")
+            };
+            return Err(Error::new(
+                format!("group `{}` derives `PartialEq`, but its {} {} {}",
+                        name, nodes, missing.join(", "), verb),
+                pos,
+            ));
+        }
+    }
+
     let text_ident = Ident::new("Text");
-    if groups.iter().any(|group| group.nodes.contains(&text_ident)) {
+    let text_child = nodes.iter().flat_map(|node| &node.slots).any(|slot| match slot.child {
+        Child::Node(ref name) => *name == text_ident,
+        _ => false,
+    });
+    if text_child || groups.iter().any(|group| group.nodes.contains(&text_ident)) {
         nodes_by_name.insert(text_ident, Node::Text);
     }
 
-    ParsedData {
+    Ok(ParsedData {
         node_by_name: nodes_by_name,
         groups: groups,
-    }
+        export: export,
+    })
 }
 
 mod parser {
-    use parser::{Child, NodeNormal, ChildType};
-    use syn::parse::{ident, path};
-    use syn::Ident;
+    use parser::{Child, NodeNormal, ChildType, Slot};
+    use error::Pos;
+    use synom::IResult;
+    use synom::space::skip_whitespace;
+    use syn::parse::{ident, path, tt};
+    use syn::{parse_outer_attr, Attribute, AttrStyle, Ident, Lit, MetaItem, Path, StrStyle,
+              TokenTree};
+    use quote::Tokens;
+    use quote::ToTokens;
 
     #[derive(Debug, Clone, Copy)]
     pub enum GroupOp {
@@ -155,69 +633,172 @@ mod parser {
 
     #[derive(Debug, Clone)]
     pub struct Group {
+        pub attrs: Vec<Attribute>,
         pub name: Ident,
-        pub children: Vec<(GroupOp, Child)>,
+        pub pos: Pos,
+        pub children: Vec<(GroupOp, Child, Pos)>,
     }
 
-    named!(parse_child -> Child,
+    /// Position of the next token.
+    fn position(input: &str) -> IResult<&str, Pos> {
+        let input = skip_whitespace(input);
+        IResult::Done(input, Pos(input.len()))
+    }
+
+    named!(parse_child -> (Child, Pos),
+        do_parse!(
+            pos: position >>
+            child: alt!(
+                preceded!(punct!("@"), ident) => {|g| Child::Group(g)}
+                |
+                ident => {|n| Child::Node(n)}
+            ) >>
+            (child, pos)
+        )
+    );
+
+    fn to_attr(tt: TokenTree) -> Attribute {
+        let mut tokens = Tokens::new();
+        tokens.append("#");
+        tt.to_tokens(&mut tokens);
+        parse_outer_attr(tokens.as_str()).expect("vtree define_nodes attribute")
+    }
+
+    /// Doc comments as `#[doc = "..."]`, which unlike a sugared doc comment can be followed by
+    /// other tokens on the same line when generating code.
+    fn doc_attr(doc: &str) -> Attribute {
+        Attribute {
+            style: AttrStyle::Outer,
+            value: MetaItem::NameValue("doc".into(), Lit::Str(doc.into(), StrStyle::Cooked)),
+            is_sugared_doc: false,
+        }
+    }
+
+    // `TokenStream::to_string` keeps doc comments sugared, as `///` and `/** */`
+    named!(parse_attr -> Attribute,
+        alt!(
+            preceded!(punct!("#"), tt) => {to_attr}
+            |
+            delimited!(punct!("///"), take_until!("\n"), tag!("\n")) => {doc_attr}
+            |
+            delimited!(punct!("/**"), take_until!("*/"), tag!("*/")) => {doc_attr}
+        )
+    );
+
+    named!(parse_slot_ty -> (ChildType, (Child, Pos)),
+        do_parse!(
+            child_ty: alt!(
+                keyword!("mul") => {|_| ChildType::Multi}
+                |
+                keyword!("opt") => {|_| ChildType::Optional}
+                |
+                epsilon!() => {|_| ChildType::Single}
+            ) >>
+            child: parse_child >>
+            (child_ty, child)
+        )
+    );
+
+    named!(parse_named_slot -> Slot,
+        do_parse!(
+            pos: position >>
+            name: ident >>
+            punct!(":") >>
+            slot_ty: parse_slot_ty >>
+            (Slot {
+                name: Some(name),
+                ty: slot_ty.0,
+                child: (slot_ty.1).0,
+                pos: pos,
+                child_pos: (slot_ty.1).1,
+            })
+        )
+    );
+
+    named!(parse_slots -> Vec<Slot>,
         alt!(
-            preceded!(punct!("@"), ident) => {|g| Child::Group(g)}
+            preceded!(punct!(":"), parse_slot_ty) => {|(ty, (child, pos))| vec![Slot {
+                name: None,
+                ty: ty,
+                child: child,
+                pos: pos,
+                child_pos: pos,
+            }]}
+            |
+            delimited!(
+                punct!("{"),
+                terminated_list!(punct!(","), parse_named_slot),
+                punct!("}")
+            )
             |
-            ident => {|n| Child::Node(n)}
+            epsilon!() => {|_| Vec::new()}
         )
     );
 
-    named!(parse_nodes -> Vec<NodeNormal>,
+    named!(pub parse_nodes -> Vec<NodeNormal>,
         terminated_list!(punct!(","), do_parse!(
+            attrs: many0!(parse_attr) >>
+            pos: position >>
             name: ident >>
-            params_ty: option!(delimited!(punct!("<"), path, punct!(">"))) >>
-            child: option!(do_parse!(
-                punct!(":") >>
-                child_ty: alt!(
-                    keyword!("mul") => {|_| ChildType::Multi}
-                    |
-                    keyword!("opt") => {|_| ChildType::Optional}
-                    |
-                    epsilon!() => {|_| ChildType::Single}
-                ) >>
-                child: parse_child >>
-                (child_ty, child)
-            )) >>
+            params: option!(delimited!(punct!("<"), tuple!(position, path), punct!(">"))) >>
+            slots: parse_slots >>
             (NodeNormal {
+                attrs: attrs,
                 name: name,
-                params_ty: params_ty,
-                child: child,
+                pos: pos,
+                params_ty: params.as_ref().map(|&(_, ref params_ty)| params_ty.clone()),
+                params_pos: params.map(|(params_pos, _)| params_pos),
+                slots: slots,
             })
         ))
     );
 
-    named!(parse_groups -> Vec<Group>,
+    named!(pub parse_groups -> Vec<Group>,
         terminated_list!(punct!(","), do_parse!(
+            attrs: many0!(parse_attr) >>
+            pos: position >>
             name: ident >>
             punct!(":") >>
             children: many0!(alt!(
-                preceded!(option!(punct!("+")), parse_child) => {|c| (GroupOp::Add, c)}
+                preceded!(option!(punct!("+")), parse_child) => {|(c, p)| (GroupOp::Add, c, p)}
                 |
-                preceded!(punct!("-"), parse_child) => {|c| (GroupOp::Sub, c)}
+                preceded!(punct!("-"), parse_child) => {|(c, p)| (GroupOp::Sub, c, p)}
             )) >>
             (Group {
+                attrs: attrs,
                 name: name,
+                pos: pos,
                 children: children,
             })
         ))
     );
 
-    named!(pub parse -> (Vec<NodeNormal>, Vec<Group>),
+    named!(pub parse_nodes_start -> (),
         do_parse!(
             keyword!("nodes") >>
             punct!("{") >>
-            nodes: parse_nodes >>
-            punct!("}") >>
+            ()
+        )
+    );
+
+    named!(pub parse_groups_start -> Option<Pos>,
+        do_parse!(
             keyword!("groups") >>
+            fixpoint: option!(delimited!(punct!("("), terminated!(position, keyword!("fixpoint")), punct!(")"))) >>
             punct!("{") >>
-            groups: parse_groups >>
-            punct!("}") >>
-            (nodes, groups)
+            (fixpoint)
+        )
+    );
+
+    named!(pub parse_close -> &str, punct!("}"));
+
+    named!(pub parse_export -> (Ident, Option<(Pos, Path)>),
+        do_parse!(
+            keyword!("export") >>
+            name: ident >>
+            crate_path: option!(delimited!(punct!("("), tuple!(position, path), punct!(")"))) >>
+            punct!(";") >>
+            (name, crate_path)
         )
     );
 }
@@ -0,0 +1,107 @@
+//! Maps byte offsets into the stringified macro input, which the string based parsers report
+//! positions in, back to the spans of the input tokens. Shared with `vtree_markup`, which
+//! includes this file.
+
+use proc_macro::{Delimiter, Span, TokenStream, TokenTree};
+
+/// Span of the first token at or after the byte `offset` into the stringified `input`.
+pub fn span_at(input: &TokenStream, offset: usize) -> Span {
+    let mut atoms = Vec::new();
+    flatten(input.clone(), &mut atoms);
+
+    // the stringified input is the atoms in order separated by whitespace only, doc comments
+    // stay sugared
+    let input = input.to_string();
+    let mut cursor = 0;
+    let mut found = None;
+    let mut atoms = atoms.iter();
+    while let Some(atom) = atoms.next() {
+        let rest = &input[cursor..];
+        let trimmed = rest.trim_start();
+        let doc_end = if atom.doc_len == 0 {
+            None
+        } else if trimmed.starts_with("///") {
+            Some(trimmed.find('\n').unwrap_or(trimmed.len()))
+        } else if trimmed.starts_with("/**") {
+            trimmed.find("*/").map(|end| end + 2)
+        } else {
+            None
+        };
+        let (pos, len) = match doc_end {
+            Some(end) => {
+                for _ in 0..atom.doc_len {
+                    atoms.next();
+                }
+                (cursor + rest.len() - trimmed.len(), end)
+            }
+            None => match rest.find(atom.text.as_str()) {
+                Some(pos) => (cursor + pos, atom.text.len()),
+                None => break,
+            },
+        };
+        cursor = pos + len;
+        found = Some(atom.span);
+        if offset <= pos {
+            break;
+        }
+    }
+    found.unwrap_or_else(Span::call_site)
+}
+
+struct Atom {
+    text: String,
+    span: Span,
+    /// For the `#` of a `#[doc = ".."]`, the number of atoms of its `[..]`, which are
+    /// stringified as a sugared doc comment.
+    doc_len: usize,
+}
+
+fn atom(text: String, span: Span) -> Atom {
+    Atom {
+        text: text,
+        span: span,
+        doc_len: 0,
+    }
+}
+
+/// Collects the leaf tokens and delimiters of `stream` in order with their spans.
+fn flatten(stream: TokenStream, atoms: &mut Vec<Atom>) {
+    let mut stream = stream.into_iter().peekable();
+    while let Some(tt) = stream.next() {
+        match tt {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => {
+                        flatten(group.stream(), atoms);
+                        continue;
+                    }
+                };
+                atoms.push(atom(open.to_string(), group.span_open()));
+                flatten(group.stream(), atoms);
+                atoms.push(atom(close.to_string(), group.span_close()));
+            }
+            TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                let is_doc = match stream.peek() {
+                    Some(&TokenTree::Group(ref attr)) => {
+                        attr.delimiter() == Delimiter::Bracket &&
+                        attr.stream().into_iter().next().map_or(false, |tt| tt.to_string() == "doc")
+                    }
+                    _ => false,
+                };
+                let index = atoms.len();
+                atoms.push(atom(punct.to_string(), punct.span()));
+                if is_doc {
+                    let start = atoms.len();
+                    if let Some(attr) = stream.next() {
+                        flatten(Some(attr).into_iter().collect(), atoms);
+                    }
+                    atoms[index].doc_len = atoms.len() - start;
+                }
+            }
+            tt => atoms.push(atom(tt.to_string(), tt.span())),
+        }
+    }
+}
@@ -7,6 +7,7 @@ use vtree_markup::markup;
 
 fn main() {
     let a = 42;
+    let items = vec![(1, "one"), (2, "two")];
     markup! {
         Container {
             Button@"1. button" test="foo" bool_true? Label "foo bar"
@@ -18,6 +19,21 @@ fn main() {
                 })
                 Label {"foo bar: `"@1 (a)@2 "`"@3}
             Button@"3. button" test="foo" Label {"foo bar: `" (a) "`"}
+            Button@"4. button" on_click=|e| println!("clicked {:?}", e) Label "click"
+            <Counter>(a)@"counter"
+            Slider@-1 value=-0.5 step=1 align=Align::Center margin=Edges { top: 1.0, ..Edges::default() } /
+            if a > 40 {
+                Label@"size" "big"
+            } else {
+                Label@"size" "small"
+            }
+            match a {
+                42 => Label@"answer" "yes",
+                _ => {}
+            }
+            for &(id, name) in items.iter() @(id) {
+                Label (name)
+            }
         }
     }
 }
@@ -0,0 +1,82 @@
+use proc_macro::{Delimiter, Diagnostic, Group, Level, Literal, Punct, Spacing, Span,
+                 TokenStream, TokenTree};
+use proc_macro;
+use span::span_at;
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    msg: String,
+    help: Option<String>,
+    /// Byte offset into the stringified input, the first token at or after it is used.
+    offset: usize,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(msg: S, offset: usize) -> Error {
+        Error {
+            msg: msg.into(),
+            help: None,
+            offset: offset,
+        }
+    }
+
+    /// Suggestion shown below the error.
+    pub fn help<S: Into<String>>(mut self, help: S) -> Error {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Renders the error as `compile_error!` spanned at its offset within `input`.
+    pub fn to_compile_error(&self, input: &TokenStream) -> TokenStream {
+        let span = self.span(input);
+
+        let msg = match self.help {
+            Some(ref help) => format!("{}\nhelp: {}", self.msg, help),
+            None => self.msg.clone(),
+        };
+        let mut msg = Literal::string(&msg);
+        msg.set_span(span);
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(span);
+        let mut body = Group::new(Delimiter::Brace, TokenTree::Literal(msg).into());
+        body.set_span(span);
+
+        vec![
+            TokenTree::Ident(proc_macro::Ident::new("compile_error", span)),
+            TokenTree::Punct(bang),
+            TokenTree::Group(body),
+        ].into_iter()
+            .collect()
+    }
+
+    /// Emits the error as a compiler warning spanned at its offset within `input`.
+    pub fn emit_warning(&self, input: &TokenStream) {
+        let diagnostic = Diagnostic::spanned(self.span(input), Level::Warning, self.msg.clone());
+        match self.help {
+            Some(ref help) => diagnostic.help(help.clone()),
+            None => diagnostic,
+        }.emit();
+    }
+
+    fn span(&self, input: &TokenStream) -> Span {
+        span_at(input, self.offset)
+    }
+}
+
+/// Sets the span of all tokens in `stream` to `span`, so errors in generated code are reported
+/// at the input it was generated for.
+pub fn respan(stream: TokenStream, span: Span) -> TokenStream {
+    stream.into_iter()
+        .map(|tt| match tt {
+            TokenTree::Group(group) => {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::Group(respanned)
+            }
+            mut tt => {
+                tt.set_span(span);
+                tt
+            }
+        })
+        .collect()
+}
@@ -1,12 +1,25 @@
 use parser::{Node, TextValue, Value, Params};
 use quote::{Tokens, Ident};
+use syn::{IntTy, Lit};
 
 fn render_param_value(val: &Value) -> Tokens {
     match val {
         &Value::String(ref v) => quote!{#v},
-        &Value::Int(ref v) => quote!{#v},
+        &Value::Int { negative, value, suffix } => {
+            let lit = Lit::Int(value, suffix);
+            if negative {
+                quote!{(-#lit)}
+            } else {
+                quote!{#lit}
+            }
+        }
+        &Value::Float(ref v) => {
+            let v = Ident::new(v.to_string());
+            quote!{(#v)}
+        }
+        &Value::Char(ref v) => quote!{#v},
         &Value::Bytes(ref v) => quote!{#v},
-        &Value::Expr(ref v) => {
+        &Value::Expr(ref v) | &Value::Literal(ref v) | &Value::Closure(ref v) => {
             let v = Ident::new(v.to_string());
             quote!{(#v)}
         }
@@ -14,23 +27,173 @@ fn render_param_value(val: &Value) -> Tokens {
     }
 }
 
+/// Argument of a params setter. Closures are passed as is for the generic `F: Fn(..)` event
+/// setters, as are unsuffixed numbers to take the type of the param.
+fn render_setter_arg(val: &Value) -> Tokens {
+    let rendered = render_param_value(val);
+    match *val {
+        Value::Closure(_) | Value::Float(_) | Value::Int { suffix: IntTy::Unsuffixed, .. } => {
+            rendered
+        }
+        _ => quote!{#rendered.into()},
+    }
+}
 
+/// Renders a key, unsuffixed integers become a `Key::U64` or for negative ones a `Key::I64`
+/// and chars a `Key::Str`.
 fn render_value(val: &Value) -> Tokens {
     match val {
-        &Value::String(ref v) => quote!{#v},
-        &Value::Int(ref v) => quote!{#v},
-        &Value::Bytes(ref v) => quote!{#v},
-        &Value::Expr(ref v) => {
-            let v = Ident::new(v.to_string());
-            quote!{(#v)}
+        &Value::Int { negative, value, suffix } => {
+            let suffix = match suffix {
+                IntTy::Unsuffixed if negative => IntTy::I64,
+                IntTy::Unsuffixed => IntTy::U64,
+                suffix => suffix,
+            };
+            render_param_value(&Value::Int {
+                negative: negative,
+                value: value,
+                suffix: suffix,
+            })
         }
-        &Value::Bool(ref v) => quote!{#v},
+        &Value::Char(ref v) => {
+            let v = v.to_string();
+            quote!{#v}
+        }
+        val => render_param_value(val),
+    }
+}
+
+/// Appends the `add` calls of `children` to the children builder expression `builder`.
+/// Unkeyed nodes use `default_key` within `for` and otherwise `Key::Auto` with their `index`
+/// among the unkeyed nodes of the template, so it doesn't depend on the taken branches.
+fn render_children(builder: Tokens, children: Vec<Node>, index: &mut usize,
+                   default_key: Option<&Value>) -> Tokens {
+    children.into_iter().fold(builder, |builder, child| match child {
+        Node::Text { value: TextValue::Expr(true, _), .. } => {
+            let child_rendered = render_node(child);
+            quote!{
+                #builder.add_all(#child_rendered)
+            }
+        }
+        Node::Node { .. } | Node::Text { .. } | Node::Widget { .. } => {
+            let key = match child {
+                Node::Node { ref key, .. } |
+                Node::Text { ref key, .. } |
+                Node::Widget { ref key, .. } => key,
+                _ => unreachable!(),
+            };
+            let key = match key.as_ref().or(default_key) {
+                Some(key) => render_value(key),
+                None => {
+                    let auto_key = *index as u64;
+                    *index += 1;
+                    quote!{::vtree::key::Key::Auto(#auto_key)}
+                }
+            };
+            let child_rendered = render_node(child);
+            quote!{
+                #builder.add(#key.into(), #child_rendered)
+            }
+        }
+        Node::If { cond, then, else_ } => {
+            let cond = Ident::new(cond);
+            let then = render_children(quote!{builder_}, then, index, default_key);
+            let else_ = render_children(quote!{builder_}, else_, index, default_key);
+            quote!{
+                {
+                    let builder_ = #builder;
+                    if #cond { #then } else { #else_ }
+                }
+            }
+        }
+        Node::Match { expr, arms } => {
+            let expr = Ident::new(expr);
+            let arms = arms.into_iter().map(|(arm, children)| {
+                let arm = Ident::new(arm);
+                let children = render_children(quote!{builder_}, children, index, default_key);
+                quote!{
+                    #arm => #children,
+                }
+            }).collect::<Vec<_>>();
+            quote!{
+                {
+                    let builder_ = #builder;
+                    match #expr { #(#arms)* }
+                }
+            }
+        }
+        Node::For { pat, iter, key, children } => {
+            let pat = Ident::new(pat);
+            let iter = Ident::new(iter);
+            let children = render_children(quote!{builder_}, children, &mut 0, Some(&key));
+            quote!{
+                {
+                    let mut builder_ = #builder;
+                    for #pat in #iter {
+                        builder_ = #children;
+                    }
+                    builder_
+                }
+            }
+        }
+    })
+}
+
+/// Pushes the nesting checks of the node children of `node` and its descendants, `parent`
+/// being the node whose children `node` is part of, with the named slot holding it. Each check
+/// comes with the offset of the child it checks.
+pub fn render_checks(node: &Node, parent: Option<(&str, Option<&str>)>,
+                     checks: &mut Vec<(usize, Tokens)>) {
+    match *node {
+        Node::Node { ref name, offset, ref children, ref slots, .. } => {
+            if let Some((parent, slot)) = parent {
+                let parent = Ident::new(parent);
+                let child = Ident::new(name.as_str());
+                let check = match slot {
+                    Some(slot) => {
+                        let slot = Ident::new(slot);
+                        quote!{
+                            ::vtree::node::check_slot::<#parent, _>(|| #parent::builder().#slot())
+                                .child::<#child>();
+                        }
+                    }
+                    None => quote!{
+                        ::vtree::node::check_child::<#parent, #child>();
+                    },
+                };
+                checks.push((offset, check));
+            }
+            for child in children {
+                render_checks(child, Some((name, None)), checks);
+            }
+            for &(ref slot, ref children) in slots {
+                for child in children {
+                    render_checks(child, Some((name, Some(slot))), checks);
+                }
+            }
+        }
+        Node::If { ref then, ref else_, .. } => {
+            for child in then.iter().chain(else_) {
+                render_checks(child, parent, checks);
+            }
+        }
+        Node::Match { ref arms, .. } => {
+            for child in arms.iter().flat_map(|&(_, ref children)| children) {
+                render_checks(child, parent, checks);
+            }
+        }
+        Node::For { ref children, .. } => {
+            for child in children {
+                render_checks(child, parent, checks);
+            }
+        }
+        Node::Text { .. } | Node::Widget { .. } => {}
     }
 }
 
 pub fn render_node(node: Node) -> Tokens {
     match node {
-        Node::Node {name, params, children, ..} => {
+        Node::Node {name, params, children, slots, ..} => {
             let name = Ident::new(name);
 
             let maybe_params = match params {
@@ -40,9 +203,9 @@ pub fn render_node(node: Node) -> Tokens {
                     } else {
                         let kvs = kvs.into_iter().map(|(key, val)| {
                             let set_key = Ident::new(format!("set_{}", key));
-                            let val = render_param_value(&val);
+                            let val = render_setter_arg(&val);
                             quote!{
-                                .#set_key(#val.into())
+                                .#set_key(#val)
                             }
                         });
 
@@ -54,57 +217,45 @@ pub fn render_node(node: Node) -> Tokens {
                     }
                 }
                 Params::Whole(value) => {
-                    let val = render_param_value(&value);
+                    let val = render_setter_arg(&value);
                     Some(quote!{
-                        .set_params(#val.into())
+                        .set_params(#val)
                     })
                 }
             };
 
-            let maybe_children = if children.is_empty() {
-                None
-            } else {
-                let children = children.into_iter().enumerate().map(|(index, child)| {
-                    match &child {
-                        &Node::Text{value: TextValue::Expr(true, _), ..} => {
-                            let child_rendered = render_node(child);
-                            quote!{
-                                .add_all(#child_rendered)
-                            }
-                        }
-                        _ => {
-                            let key = {
-                                let key = match child {
-                                    Node::Node {ref key, ..} => key,
-                                    Node::Text {ref key, ..} => key,
-                                };
-                                render_value(key
-                                        .as_ref()
-                                        .unwrap_or(&Value::Int(u64::max_value() - index as u64)))
-                            };
-                            let child_rendered = render_node(child);
-                            quote!{
-                                .add(#key.into(), #child_rendered)
-                            }
-                        }
-                    }
-                });
-
-                Some(quote!{
-                    .children()
-                    #(#children)*
-                    .build()
-                })
+            let mut builder = quote!{
+                #name::builder()
+                    #maybe_params
             };
+            if !children.is_empty() {
+                let children_builder = quote!{#builder.children()};
+                let children = render_children(children_builder, children, &mut 0, None);
+                builder = quote!{#children.build()};
+            }
+            for (slot, children) in slots {
+                let slot = Ident::new(slot);
+                let slot_builder = quote!{#builder.#slot()};
+                let children = render_children(slot_builder, children, &mut 0, None);
+                builder = quote!{#children.build()};
+            }
 
             quote!{
-                #name::builder()
-                    #maybe_params
-                    #maybe_children
+                #builder
                     .build()
                     .into()
             }
         },
+        Node::Widget { path, input, .. } => {
+            let path = Ident::new(path);
+            let input = Ident::new(input);
+            quote!{
+                ::vtree::widget::WidgetData::<#path>(#input).into()
+            }
+        }
+        Node::If { .. } | Node::Match { .. } | Node::For { .. } => {
+            unreachable!("control flow is rendered as part of the children")
+        }
         Node::Text {value, ..} => {
             match value {
                 TextValue::String(v) => quote!{#v.into()},
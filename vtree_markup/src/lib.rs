@@ -1,4 +1,5 @@
 #![feature(proc_macro)]
+#![feature(proc_macro_diagnostic)]
 
 #[macro_use]
 extern crate quote;
@@ -7,18 +8,34 @@ extern crate syn;
 extern crate synom;
 extern crate proc_macro;
 
+mod error;
+#[path = "../../vtree_macros/src/span.rs"]
+mod span;
 mod parser;
 mod generator;
 
-use parser::parse_node;
-use generator::render_node;
-use proc_macro::TokenStream;
+use error::respan;
+use span::span_at;
+use parser::parse;
+use generator::{render_checks, render_node};
+use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
 
 #[proc_macro]
 pub fn markup(input: TokenStream) -> TokenStream {
-    let input = input.to_string();
-    let node = parse_node(&input).expect("vtree markup");
-    let rendered_node = render_node(node).into_string();
-    println!("{}", rendered_node);
-    rendered_node.parse().unwrap()
+    let (node, warnings) = match parse(&input.to_string()) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.to_compile_error(&input),
+    };
+    for warning in warnings {
+        warning.emit_warning(&input);
+    }
+    // the nesting checks are spanned at their child, so `ChildOf` errors point at it
+    let mut checks = Vec::new();
+    render_checks(&node, None, &mut checks);
+    let mut output: TokenStream = checks
+        .into_iter()
+        .map(|(offset, check)| respan(check.into_string().parse().unwrap(), span_at(&input, offset)))
+        .collect();
+    output.extend(render_node(node).into_string().parse::<TokenStream>());
+    TokenTree::Group(Group::new(Delimiter::Brace, output)).into()
 }
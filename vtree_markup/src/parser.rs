@@ -1,15 +1,32 @@
-use syn::{StrLit, IntLit, ByteStrLit};
-use syn::parse::{ident, path, int, string, byte_string, expr, boolean};
+use syn::{StrLit, ByteStrLit};
+use syn::{self, ExprKind, IntTy, Lit, Pat};
+use syn::parse::{ident, path, int, float, character, string, byte_string, expr, boolean, pat, tt};
+use synom::IResult;
+use synom::helper::{keyword, punct};
+use synom::space::skip_whitespace;
+use error::Error;
+use std::cell::{Cell, RefCell};
 use quote::Tokens;
 use quote::ToTokens;
 
 #[derive(Debug)]
 pub enum Value {
     String(String),
-    Int(u64),
+    Int {
+        negative: bool,
+        value: u64,
+        suffix: IntTy,
+    },
+    /// Float literal as written, with its sign.
+    Float(String),
+    Char(char),
     Bytes(Vec<u8>),
     Expr(String),
     Bool(bool),
+    /// Path constant, array or struct literal as written, e.g. `Align::Center`.
+    Literal(String),
+    /// `|e| ..` passed to the setter as is, e.g. for the event setters of `define_params!`.
+    Closure(String),
 }
 
 #[derive(Debug)]
@@ -28,14 +45,93 @@ pub enum Params {
 pub enum Node {
     Node {
         name: String,
+        /// Offset of the name, nesting errors are reported there.
+        offset: usize,
         key: Option<Value>,
         params: Params,
         children: Vec<Node>,
+        /// `slot: { .. }` or `slot: Child /` blocks, the children of the named slots.
+        slots: Vec<(String, Vec<Node>)>,
     },
     Text {
         value: TextValue,
         key: Option<Value>,
     },
+    /// `<MyWidget>(input)`, rendered as `WidgetData::<MyWidget>(input)`.
+    Widget {
+        path: String,
+        input: String,
+        key: Option<Value>,
+    },
+    /// `if cond { .. } else { .. }`, `else if` is an `If` as the only `else_` child.
+    If {
+        cond: String,
+        then: Vec<Node>,
+        else_: Vec<Node>,
+    },
+    /// `match expr { pat if guard => { .. } }`, arms hold their pattern with the guard.
+    Match {
+        expr: String,
+        arms: Vec<(String, Vec<Node>)>,
+    },
+    /// `for pat in iter @key { .. }`, the key is used for the nodes without their own, whose keys
+    /// have to be expressions to differ between the items.
+    For {
+        pat: String,
+        iter: String,
+        key: Value,
+        children: Vec<Node>,
+    },
+}
+
+fn to_string<T: ToTokens>(t: &T) -> String {
+    let mut tokens = Tokens::new();
+    t.to_tokens(&mut tokens);
+    tokens.into_string()
+}
+
+/// Expression up to the first of `stops` outside of any delimiters. Like in Rust a struct
+/// literal in front of a block has to be parenthesized.
+fn expr_until<'a>(input: &'a str, stops: &[&str]) -> IResult<&'a str, String> {
+    let input = skip_whitespace(input);
+    let bytes = input.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    let mut end = None;
+    while i < bytes.len() {
+        if depth == 0 && stops.iter().any(|stop| input[i..].starts_with(stop)) {
+            end = Some(i);
+            break;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'\'' if bytes.get(i + 2) == Some(&b'\'') => i += 2,
+            _ => {}
+        }
+        i += 1;
+    }
+    match end {
+        Some(end) => match syn::parse_expr(&input[..end]) {
+            Ok(e) => IResult::Done(&input[end..], to_string(&e)),
+            Err(_) => IResult::Error,
+        },
+        None => IResult::Error,
+    }
 }
 
 named!(parse_expr -> String,
@@ -51,82 +147,554 @@ named!(parse_expr -> String,
     )
 );
 
+named!(parse_closure -> String,
+    do_parse!(
+        peek!(alt!(keyword!("move") | punct!("|"))) >>
+        e: expr >>
+        (to_string(&e))
+    )
+);
+
+/// Integer or float literal, optionally negative.
+fn parse_number(input: &str) -> IResult<&str, Value> {
+    let (rest, negative) = match punct(input, "-") {
+        IResult::Done(rest, _) => (rest, true),
+        IResult::Error => (input, false),
+    };
+    if let IResult::Done(rest, v) = float(rest) {
+        let lit = to_string(&Lit::Float(v.value, v.suffix));
+        let value = if negative { format!("-{}", lit) } else { lit };
+        return IResult::Done(rest, Value::Float(value));
+    }
+    match int(rest) {
+        IResult::Done(rest, v) => {
+            IResult::Done(rest, Value::Int {
+                negative: negative,
+                value: v.value,
+                suffix: v.suffix,
+            })
+        }
+        IResult::Error => IResult::Error,
+    }
+}
+
+/// Array literal, path constant or struct literal. The `{ .. }` following a path is taken as
+/// the children of a node unless the two form a struct literal.
+fn parse_literal(input: &str) -> IResult<&str, Value> {
+    let is_literal = |text: &str, kind: fn(&ExprKind) -> bool| {
+        syn::parse_expr(text).map(|e| kind(&e.node)).unwrap_or(false)
+    };
+    if skip_whitespace(input).starts_with('[') {
+        return match tt(input) {
+            IResult::Done(rest, array) => {
+                let array = to_string(&array);
+                let is_array = is_literal(&array, |e| match *e {
+                    ExprKind::Array(..) | ExprKind::Repeat(..) => true,
+                    _ => false,
+                });
+                if is_array {
+                    IResult::Done(rest, Value::Literal(array))
+                } else {
+                    IResult::Error
+                }
+            }
+            IResult::Error => IResult::Error,
+        };
+    }
+    let (rest, path) = match path(input) {
+        IResult::Done(rest, path) => (rest, to_string(&path)),
+        IResult::Error => return IResult::Error,
+    };
+    if skip_whitespace(rest).starts_with('{') {
+        if let IResult::Done(after, fields) = tt(rest) {
+            let literal = format!("{} {}", path, to_string(&fields));
+            if is_literal(&literal, |e| match *e {
+                ExprKind::Struct(..) => true,
+                _ => false,
+            }) {
+                return IResult::Done(after, Value::Literal(literal));
+            }
+        }
+    }
+    IResult::Done(rest, Value::Literal(path))
+}
+
 named!(parse_value -> Value,
     alt!(
         string => {|v: StrLit| Value::String(v.value)}
         |
-        int => {|v: IntLit| Value::Int(v.value)} // TODO: negative ints
+        parse_number
         |
         parse_expr => {|e| Value::Expr(e)}
         |
         boolean => {|v| Value::Bool(v)}
         |
         byte_string => {|v: ByteStrLit| Value::Bytes(v.value)}
+        |
+        character => {|v| Value::Char(v)}
+        |
+        parse_closure => {|e| Value::Closure(e)}
+        |
+        parse_literal
     )
 );
 
-named!(pub parse_node -> Node,
+named!(parse_cond -> String,
     alt!(
         do_parse!(
-            value: alt!(
-                string => {|v: StrLit| TextValue::String(v.value)}
-                |
-                tuple!(option!(punct!("+")), parse_expr) =>
-                    {|(add, e): (Option<_>, _)| TextValue::Expr(add.is_some(), e)}
-            ) >>
-            key: option!(preceded!(punct!("@"), parse_value)) >>
-            (
-                Node::Text {
-                    value: value,
-                    key: key,
-                }
-            )
+            keyword!("let") >>
+            pat: pat >>
+            punct!("=") >>
+            e: call!(expr_until, &["{"]) >>
+            (format!("let {} = {}", to_string(&pat), e))
         )
         |
-        do_parse!(
-            name: path >>
-            key: option!(preceded!(punct!("@"), parse_value)) >>
-            params: alt!(
-                do_parse!(
-                    value: preceded!(punct!("="), parse_value) >>
-                    (Params::Whole(value))
-                )
-                |
-                do_parse!(
-                    kvs: many0!(do_parse!(
-                        name: ident >>
-                        value: alt!(
-                            preceded!(punct!("="), parse_value)
-                            |
-                            punct!("?") => {|_| Value::Bool(true)}
-                        ) >>
-                        ((name.to_string(), value))
-                    )) >>
-                    (Params::KeyValue(kvs))
-                )
-            ) >>
-            children: alt!(
-                punct!("/") => {|_| vec![]}
-                |
-                parse_node => {|n| vec![n]}
-                |
-                do_parse!(
-                    punct!("{") >>
-                    children: many0!(parse_node) >>
-                    punct!("}") >>
-                    (children)
-                )
-            ) >>
-            ({
-                let mut name_token = Tokens::new();
-                name.to_tokens(&mut name_token);
-                Node::Node {
-                    name: name_token.into_string(),
-                    key: key,
-                    params: params,
-                    children: children,
-                }
-            })
-        )
+        call!(expr_until, &["{"])
     )
 );
+
+type PResult<'a, T> = Result<(&'a str, T), Error>;
+
+/// Input of `markup!` with the warnings found while parsing it.
+struct Input<'i> {
+    text: &'i str,
+    warnings: RefCell<Vec<Error>>,
+    /// Whether the nodes being parsed are added by a `for`, which repeats constant keys.
+    in_for: Cell<bool>,
+}
+
+fn offset(input: &Input, rest: &str) -> usize {
+    input.text.len() - rest.len()
+}
+
+fn expected(input: &Input, rest: &str, expected: &str) -> Error {
+    let msg = if rest.trim().is_empty() {
+        format!("unexpected end of input, expected {}", expected)
+    } else {
+        format!("expected {}", expected)
+    };
+    Error::new(msg, offset(input, skip_whitespace(rest)))
+}
+
+fn leaf<'a, O>(rest: &'a str, parser: fn(&'a str) -> IResult<&'a str, O>) -> Option<(&'a str, O)> {
+    match parser(rest) {
+        IResult::Done(rest, o) => Some((rest, o)),
+        IResult::Error => None,
+    }
+}
+
+fn tag<'a>(rest: &'a str, token: &'static str) -> Option<&'a str> {
+    match punct(rest, token) {
+        IResult::Done(rest, _) => Some(rest),
+        IResult::Error => None,
+    }
+}
+
+fn kw<'a>(rest: &'a str, token: &'static str) -> Option<&'a str> {
+    match keyword(rest, token) {
+        IResult::Done(rest, _) => Some(rest),
+        IResult::Error => None,
+    }
+}
+
+fn expect<'a>(input: &Input, rest: &'a str, token: &'static str) -> Result<&'a str, Error> {
+    tag(rest, token).ok_or_else(|| expected(input, rest, &format!("`{}`", token)))
+}
+
+fn value<'a>(input: &Input, rest: &'a str, what: &str) -> PResult<'a, Value> {
+    leaf(rest, parse_value).ok_or_else(|| {
+        expected(input, rest, what)
+            .help("values are literals, closures or parenthesized expressions like `(a + 1)`")
+    })
+}
+
+/// Rejects values without a `Key` conversion.
+fn check_key(input: &Input, rest: &str, key: &Value) -> Result<(), Error> {
+    let kind = match *key {
+        Value::Float(_) => "a float",
+        Value::Bool(_) => "a bool",
+        Value::Closure(_) => "a closure",
+        _ => return Ok(()),
+    };
+    Err(Error::new(format!("{} can't be used as a key", kind), offset(input, skip_whitespace(rest)))
+        .help("use an integer, string, byte string or char key"))
+}
+
+fn key<'a>(input: &Input, rest: &'a str) -> PResult<'a, Option<Value>> {
+    match tag(rest, "@") {
+        Some(rest) => {
+            let (r, key) = value(input, rest, "a key after `@`")?;
+            check_key(input, rest, &key)?;
+            let constant = match key {
+                Value::Expr(_) => false,
+                _ => true,
+            };
+            if constant && input.in_for.get() {
+                return Err(Error::new("constant key within `for` is the same for every item",
+                                      offset(input, skip_whitespace(rest)))
+                    .help("key the node by the item, e.g. `@(item.id)`"));
+            }
+            Ok((r, Some(key)))
+        }
+        None => Ok((rest, None)),
+    }
+}
+
+fn is_control_flow(rest: &str) -> bool {
+    ["if", "else", "match", "for"].iter().any(|k| kw(rest, k).is_some())
+}
+
+/// Whether `rest` starts like a node, used to tell a single child from a missing `/`.
+fn starts_node(rest: &str) -> bool {
+    leaf(rest, string).is_some() ||
+    ["(", "+", "<"].iter().any(|t| tag(rest, t).is_some()) ||
+    (!is_control_flow(rest) && leaf(rest, path).is_some())
+}
+
+/// Number of nodes added without a key by `children`, counting the largest branch of each
+/// `if` and `match`.
+fn count_unkeyed(children: &[Node]) -> usize {
+    children
+        .iter()
+        .map(|child| match *child {
+            Node::Node { key: None, .. } => 1,
+            Node::Text { value: TextValue::Expr(true, _), .. } => 0,
+            Node::Text { key: None, .. } => 1,
+            Node::Widget { key: None, .. } => 1,
+            Node::Node { .. } | Node::Text { .. } | Node::Widget { .. } | Node::For { .. } => 0,
+            Node::If { ref then, ref else_, .. } => {
+                ::std::cmp::max(count_unkeyed(then), count_unkeyed(else_))
+            }
+            Node::Match { ref arms, .. } => {
+                arms.iter().map(|&(_, ref children)| count_unkeyed(children)).max().unwrap_or(0)
+            }
+        })
+        .sum()
+}
+
+/// Records whether `children` add nodes with and without an explicit key, splices and `for`
+/// count as keyed.
+fn key_kinds(children: &[Node], keyed: &mut bool, unkeyed: &mut bool) {
+    for child in children {
+        match *child {
+            Node::Text { value: TextValue::Expr(true, _), .. } | Node::For { .. } => *keyed = true,
+            Node::Node { ref key, .. } | Node::Text { ref key, .. } | Node::Widget { ref key, .. } => {
+                if key.is_some() {
+                    *keyed = true;
+                } else {
+                    *unkeyed = true;
+                }
+            }
+            Node::If { ref then, ref else_, .. } => {
+                key_kinds(then, keyed, unkeyed);
+                key_kinds(else_, keyed, unkeyed);
+            }
+            Node::Match { ref arms, .. } => {
+                for &(_, ref children) in arms {
+                    key_kinds(children, keyed, unkeyed);
+                }
+            }
+        }
+    }
+}
+
+fn parse_text<'a>(input: &Input, rest: &'a str) -> PResult<'a, Option<TextValue>> {
+    if let Some((rest, v)) = leaf(rest, string) {
+        return Ok((rest, Some(TextValue::String(v.value))));
+    }
+    if let Some(r) = tag(rest, "+") {
+        let (rest, e) = leaf(r, parse_expr)
+            .ok_or_else(|| expected(input, r, "a parenthesized expression after `+`"))?;
+        return Ok((rest, Some(TextValue::Expr(true, e))));
+    }
+    Ok(match leaf(rest, parse_expr) {
+        Some((rest, e)) => (rest, Some(TextValue::Expr(false, e))),
+        None => (rest, None),
+    })
+}
+
+fn parse_widget<'a>(input: &Input, rest: &'a str) -> PResult<'a, Node> {
+    let (rest, widget) = leaf(rest, path)
+        .ok_or_else(|| expected(input, rest, "a widget type after `<`"))?;
+    let rest = expect(input, rest, ">")?;
+    let (rest, widget_input) = match tag(rest, "(").and_then(|r| tag(r, ")")) {
+        Some(rest) => (rest, "()".to_string()),
+        None => {
+            leaf(rest, parse_expr).ok_or_else(|| {
+                expected(input, rest, "the widget input in parentheses")
+                    .help(format!("pass the input like `<{}>(input)`", to_string(&widget)))
+            })?
+        }
+    };
+    let (rest, key) = key(input, rest)?;
+    Ok((rest, Node::Widget {
+        path: to_string(&widget),
+        input: widget_input,
+        key: key,
+    }))
+}
+
+fn parse_params<'a>(input: &Input, rest: &'a str) -> PResult<'a, Params> {
+    if let Some(rest) = tag(rest, "=") {
+        let (rest, value) = value(input, rest, "the params value after `=`")?;
+        return Ok((rest, Params::Whole(value)));
+    }
+    let mut kvs = Vec::new();
+    let mut rest = rest;
+    while let Some((r, name)) = leaf(rest, ident) {
+        let name = name.to_string();
+        let (r, value) = if let Some(r) = tag(r, "=") {
+            value(input, r, &format!("a value for `{}`", name))?
+        } else if let Some(r) = tag(r, "?") {
+            (r, Value::Bool(true))
+        } else {
+            // a single child node
+            break;
+        };
+        kvs.push((name, value));
+        rest = r;
+    }
+    Ok((rest, Params::KeyValue(kvs)))
+}
+
+/// Parses a node, text or widget.
+fn parse_node<'a>(input: &Input, rest: &'a str) -> PResult<'a, Node> {
+    if let (rest, Some(value)) = parse_text(input, rest)? {
+        let (rest, key) = key(input, rest)?;
+        return Ok((rest, Node::Text {
+            value: value,
+            key: key,
+        }));
+    }
+    if let Some(rest) = tag(rest, "<") {
+        return parse_widget(input, rest);
+    }
+
+    let start = offset(input, skip_whitespace(rest));
+    let (rest, name) = match leaf(rest, path) {
+        Some(name) if !is_control_flow(rest) => name,
+        _ => return Err(expected(input, rest, "a node, text or `<Widget>(input)`")),
+    };
+    let name = to_string(&name);
+    let (rest, key) = key(input, rest)?;
+    let (rest, params) = parse_params(input, rest)?;
+    // the children are keyed within the node, not the `for` adding it
+    let in_for = input.in_for.replace(false);
+    let (rest, children, slots) = if let Some(rest) = tag(rest, "/") {
+        (rest, vec![], vec![])
+    } else if tag(rest, "{").is_some() {
+        let (rest, (children, slots)) = parse_node_children(input, rest)?;
+        let warn_mixed_keys = |children: &[Node], what: String| {
+            let (mut keyed, mut unkeyed) = (false, false);
+            key_kinds(children, &mut keyed, &mut unkeyed);
+            if keyed && unkeyed {
+                let warning = Error::new(format!("`{}` has keyed and unkeyed {}", name, what), start)
+                    .help("unkeyed children get positional keys, give all of them a `@key` or none");
+                input.warnings.borrow_mut().push(warning);
+            }
+        };
+        warn_mixed_keys(&children, "children".to_string());
+        for &(ref slot, ref children) in &slots {
+            warn_mixed_keys(children, format!("children in slot `{}`", slot));
+        }
+        (rest, children, slots)
+    } else if starts_node(rest) {
+        let (rest, child) = parse_node(input, rest)?;
+        (rest, vec![child], vec![])
+    } else {
+        return Err(expected(input, rest, &format!("`/`, `{{` or a child after `{}`", name))
+            .help(format!("close `{0}` with `/` if it has no children, e.g. `{0} /`", name)));
+    };
+    input.in_for.set(in_for);
+    Ok((rest, Node::Node {
+        name: name,
+        offset: start,
+        key: key,
+        params: params,
+        children: children,
+        slots: slots,
+    }))
+}
+
+/// Name of the named slot block `rest` starts with, `slot:` but not a `path::`.
+fn slot_name(rest: &str) -> Option<(&str, String)> {
+    let (r, name) = leaf(rest, ident)?;
+    if tag(r, "::").is_some() {
+        return None;
+    }
+    tag(r, ":").map(|r| (r, name.to_string()))
+}
+
+/// Parses the `{ .. }` of a node, holding children and named slot blocks.
+fn parse_node_children<'a>(input: &Input, rest: &'a str)
+                           -> PResult<'a, (Vec<Node>, Vec<(String, Vec<Node>)>)> {
+    let mut rest = expect(input, rest, "{")?;
+    let mut children = Vec::new();
+    let mut slots: Vec<(String, Vec<Node>)> = Vec::new();
+    loop {
+        if let Some(rest) = tag(rest, "}") {
+            return Ok((rest, (children, slots)));
+        }
+        if rest.trim().is_empty() {
+            return Err(expected(input, rest, "`}`").help("the `{` isn't closed"));
+        }
+        if let Some((r, slot)) = slot_name(rest) {
+            if slots.iter().any(|&(ref name, _)| *name == slot) {
+                return Err(Error::new(format!("duplicate slot `{}`", slot),
+                                      offset(input, skip_whitespace(rest)))
+                    .help("put all the children of a slot in a single block"));
+            }
+            let (r, slot_children) = if tag(r, "{").is_some() {
+                parse_children(input, r)?
+            } else {
+                let (r, child) = parse_child(input, r)?;
+                (r, vec![child])
+            };
+            slots.push((slot, slot_children));
+            rest = r;
+            continue;
+        }
+        let (r, child) = parse_child(input, rest)?;
+        children.push(child);
+        rest = r;
+    }
+}
+
+/// Parses `{ .. }` holding children.
+fn parse_children<'a>(input: &Input, rest: &'a str) -> PResult<'a, Vec<Node>> {
+    let mut rest = expect(input, rest, "{")?;
+    let mut children = Vec::new();
+    loop {
+        if let Some(rest) = tag(rest, "}") {
+            return Ok((rest, children));
+        }
+        if rest.trim().is_empty() {
+            return Err(expected(input, rest, "`}`").help("the `{` isn't closed"));
+        }
+        let (r, child) = parse_child(input, rest)?;
+        children.push(child);
+        rest = r;
+    }
+}
+
+/// Parses a child within `{ .. }`, a node or a control flow block adding nodes.
+fn parse_child<'a>(input: &Input, rest: &'a str) -> PResult<'a, Node> {
+    if let Some(rest) = kw(rest, "if") {
+        parse_if(input, rest)
+    } else if let Some(rest) = kw(rest, "match") {
+        parse_match(input, rest)
+    } else if let Some(r) = kw(rest, "for") {
+        parse_for(input, rest, r)
+    } else if kw(rest, "else").is_some() {
+        Err(expected(input, rest, "a child").help("`else` has to follow the block of an `if`"))
+    } else {
+        parse_node(input, rest)
+    }
+}
+
+fn parse_if<'a>(input: &Input, rest: &'a str) -> PResult<'a, Node> {
+    let (rest, cond) = leaf(rest, parse_cond)
+        .ok_or_else(|| expected(input, rest, "a condition after `if`"))?;
+    let (rest, then) = parse_children(input, rest)?;
+    let (rest, else_) = match kw(rest, "else") {
+        Some(rest) => match kw(rest, "if") {
+            Some(rest) => {
+                let (rest, node) = parse_if(input, rest)?;
+                (rest, vec![node])
+            }
+            None => parse_children(input, rest)?,
+        },
+        None => (rest, vec![]),
+    };
+    Ok((rest, Node::If {
+        cond: cond,
+        then: then,
+        else_: else_,
+    }))
+}
+
+fn parse_match<'a>(input: &Input, rest: &'a str) -> PResult<'a, Node> {
+    let (rest, e) = leaf(rest, |r| expr_until(r, &["{"]))
+        .ok_or_else(|| expected(input, rest, "an expression after `match`"))?;
+    let mut rest = expect(input, rest, "{")?;
+    let mut arms = Vec::new();
+    loop {
+        if let Some(rest) = tag(rest, "}") {
+            return Ok((rest, Node::Match {
+                expr: e,
+                arms: arms,
+            }));
+        }
+        let mut pats = Vec::new();
+        loop {
+            let (r, p) = leaf(rest, pat).ok_or_else(|| expected(input, rest, "a pattern or `}`"))?;
+            pats.push(to_string::<Pat>(&p));
+            rest = r;
+            match tag(rest, "|") {
+                Some(r) => rest = r,
+                None => break,
+            }
+        }
+        let mut arm = pats.join(" | ");
+        if let Some(r) = kw(rest, "if") {
+            let (r, guard) = leaf(r, |r| expr_until(r, &["=>"]))
+                .ok_or_else(|| expected(input, r, "a guard expression after `if`"))?;
+            arm.push_str(" if ");
+            arm.push_str(&guard);
+            rest = r;
+        }
+        rest = expect(input, rest, "=>").map_err(|err| {
+            err.help("arms are written `pattern => Child /` or `pattern => { .. }`")
+        })?;
+        let (r, children) = if tag(rest, "{").is_some() {
+            parse_children(input, rest)?
+        } else {
+            let (r, child) = parse_child(input, rest)?;
+            (r, vec![child])
+        };
+        arms.push((arm, children));
+        rest = tag(r, ",").unwrap_or(r);
+    }
+}
+
+fn parse_for<'a>(input: &Input, start: &str, rest: &'a str) -> PResult<'a, Node> {
+    let (rest, p) = leaf(rest, pat)
+        .ok_or_else(|| expected(input, rest, "a pattern after `for`"))?;
+    let rest = kw(rest, "in").ok_or_else(|| expected(input, rest, "`in`"))?;
+    let (rest, iter) = leaf(rest, |r| expr_until(r, &["@", "{"]))
+        .ok_or_else(|| expected(input, rest, "an iterator expression after `in`"))?;
+    let rest = tag(rest, "@").ok_or_else(|| {
+        expected(input, rest, "`@` and the key of each item")
+            .help("`for` needs a key per item, e.g. `for item in items @(item.id) { .. }`")
+    })?;
+    let (r, key) = value(input, rest, "a key after `@`")?;
+    check_key(input, rest, &key)?;
+    let in_for = input.in_for.replace(true);
+    let (rest, children) = parse_children(input, r)?;
+    input.in_for.set(in_for);
+    if count_unkeyed(&children) > 1 {
+        return Err(Error::new("nodes within `for` share its key", offset(input, start))
+            .help("give all but one of them their own `@key`"));
+    }
+    Ok((rest, Node::For {
+        pat: to_string(&p),
+        iter: iter,
+        key: key,
+        children: children,
+    }))
+}
+
+/// Parses the input of `markup!`, a single root node, returns it with the warnings.
+pub fn parse(text: &str) -> Result<(Node, Vec<Error>), Error> {
+    let input = Input {
+        text: text,
+        warnings: RefCell::new(Vec::new()),
+        in_for: Cell::new(false),
+    };
+    let (rest, node) = parse_node(&input, text)?;
+    if !rest.trim().is_empty() {
+        return Err(Error::new("unexpected tokens after the root node", offset(&input, rest))
+            .help("`markup!` takes a single root node, wrap the nodes in a parent"));
+    }
+    Ok((node, input.warnings.into_inner()))
+}